@@ -1,52 +1,265 @@
-use crate::gpupipeline::State;
+use crate::error::SetupError;
+use crate::gpupipeline::{Gpu, State};
+use crate::model::{self, Geometry, Topology};
+use crate::shader;
+use crate::tonemap::Operator;
 
+use core::error::Error;
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
+use tracing::warn;
+use wgpu::{Backends, Instance, InstanceDescriptor};
 use winit::application::ApplicationHandler;
-use winit::event::WindowEvent;
+use winit::event::{ElementState, KeyEvent, MouseButton, WindowEvent};
 use winit::event_loop::ActiveEventLoop;
+use winit::keyboard::{KeyCode, PhysicalKey};
 use winit::window::{WindowAttributes, WindowId};
 
+/// Stops `-` and `=` change the exposure by.
+const EXPOSURE_STEP: f32 = 0.5;
+
 #[derive(Debug)]
 pub struct App {
-    pub componet: Option<State>,
+    /// Why the event loop was stopped, when it wasn't closed by the user.
+    pub error: Option<Box<dyn Error>>,
+    /// Created along with the first window, which it has to present to.
+    pub gpu: Option<Gpu>,
+    pub instance: Instance,
+    /// Window that submitted the latest frame, whose shader was running if
+    /// the device is lost.
+    pub last_rendered: Option<WindowId>,
+    /// Windows opened on the first resume.
+    pub pending: Vec<WindowSpec>,
+    pub windows: HashMap<WindowId, State>,
 }
 
-impl ApplicationHandler for App {
-    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+/// What a window opened from the command line shows.
+#[derive(Debug, Clone, Default)]
+pub struct WindowSpec {
+    /// `None` for the bundled shader.
+    pub shader: Option<PathBuf>,
+    /// A second shader to split the window with.
+    pub compare: Option<PathBuf>,
+    /// A model file to draw instead of the fullscreen quad.
+    pub model: Option<PathBuf>,
+    /// Bare vertices to draw instead of the fullscreen quad.
+    pub vertices: Option<u32>,
+    pub topology: Topology,
+    pub tonemap: Operator,
+    /// Stops, see [`crate::tonemap::Tonemap::exposure`].
+    pub exposure: f32,
+}
+
+impl App {
+    #[must_use]
+    pub fn new(pending: Vec<WindowSpec>) -> App {
+        App {
+            error: None,
+            gpu: None,
+            instance: Instance::new(&InstanceDescriptor {
+                backends: Backends::PRIMARY,
+                ..Default::default()
+            }),
+            last_rendered: None,
+            pending,
+            windows: HashMap::new(),
+        }
+    }
+
+    /// Opens a window showing `shader` on `geometry`, returning its id.
+    fn open_window(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        shader: shader::Source,
+        geometry: Geometry,
+    ) -> Result<WindowId, SetupError> {
         let window = Arc::new(
             event_loop
-                .create_window(WindowAttributes::default().with_transparent(true))
-                .unwrap(),
+                .create_window(
+                    WindowAttributes::default()
+                        .with_transparent(true)
+                        .with_title(shader.file_name()),
+                )
+                .map_err(SetupError::Window)?,
         );
-        let res = pollster::block_on(State::new(window.clone()));
+        let surface = self
+            .instance
+            .create_surface(Arc::clone(&window))
+            .map_err(SetupError::CreateSurface)?;
+
+        let gpu = match &mut self.gpu {
+            Some(gpu) => gpu,
+            None => self
+                .gpu
+                .insert(pollster::block_on(Gpu::new(&self.instance, &surface))?),
+        };
+
+        let state = State::new(gpu, window, surface, shader, geometry)?;
+        state.window.request_redraw();
+        let id = state.window.id();
+        self.windows.insert(id, state);
+        Ok(id)
+    }
+
+    fn open_spec(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        spec: &WindowSpec,
+    ) -> Result<(), SetupError> {
+        let geometry = match (spec.model.as_deref(), spec.vertices) {
+            (Some(path), _) => Geometry::Mesh(model::load(path)?),
+            (None, Some(count)) => Geometry::Vertices {
+                count,
+                topology: spec.topology,
+            },
+            (None, None) => Geometry::Quad,
+        };
+        // the ray marcher means nothing on a model or bare vertices, the
+        // defaults for those are more useful
+        let shader = match spec.shader.as_deref() {
+            None => shader::Source::default_for(&geometry),
+            path => shader::Source::read(path)?,
+        };
+        let id = self.open_window(event_loop, shader, geometry)?;
+        if let Some(state) = self.windows.get_mut(&id) {
+            state.tonemap.operator = spec.tonemap;
+            state.tonemap.exposure = spec.exposure;
+        }
+
+        if let Some(path) = spec.compare.as_deref() {
+            let b = shader::Source::read(Some(path))?;
+            if let (Some(gpu), Some(state)) = (self.gpu.as_ref(), self.windows.get_mut(&id)) {
+                state.window.set_title(&format!(
+                    "{} vs {}",
+                    state.shader.file_name(),
+                    b.file_name()
+                ));
+                state.compare_with(&gpu.device, b)?;
+            }
+        }
+        Ok(())
+    }
 
-        self.componet = Some(res.unwrap());
-        window.request_redraw();
+    /// Replaces the lost device with a new one, compatible with the surface
+    /// of window `id`, and rebuilds every window on it. Only the window that
+    /// drew last may fall back to its known-good shader, the others' shaders
+    /// weren't running.
+    fn recover(&mut self, id: WindowId) -> Result<(), SetupError> {
+        let Some(state) = self.windows.get(&id) else {
+            return Ok(());
+        };
+        warn!("rebuilding GPU state after losing the device");
+
+        let gpu = pollster::block_on(Gpu::new(&self.instance, &state.surface))?;
+        for (window, state) in &mut self.windows {
+            state.rebuild(&gpu, self.last_rendered == Some(*window))?;
+        }
+        self.gpu = Some(gpu);
+        Ok(())
     }
 
-    fn window_event(&mut self, event_loop: &ActiveEventLoop, _id: WindowId, event: WindowEvent) {
-        let state = self.componet.as_mut().unwrap();
+    fn fail(&mut self, event_loop: &ActiveEventLoop, error: Box<dyn Error>) {
+        self.error = Some(error);
+        event_loop.exit();
+    }
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if !self.windows.is_empty() {
+            return;
+        }
+
+        for spec in self.pending.clone() {
+            if let Err(error) = self.open_spec(event_loop, &spec) {
+                self.fail(event_loop, error.into());
+                return;
+            }
+        }
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, id: WindowId, event: WindowEvent) {
+        if self.gpu.as_ref().is_some_and(Gpu::is_lost)
+            && let Err(error) = self.recover(id)
+        {
+            self.fail(event_loop, error.into());
+            return;
+        }
+        let (Some(gpu), Some(state)) = (self.gpu.as_ref(), self.windows.get_mut(&id)) else {
+            return;
+        };
         // let input_handler = InputHandler{&state};
 
         match event {
             WindowEvent::CloseRequested => {
-                println!("The close button was pressed; stopping");
-                event_loop.exit();
+                self.windows.remove(&id);
+                if self.windows.is_empty() {
+                    event_loop.exit();
+                }
             }
             WindowEvent::RedrawRequested => {
-                state.render();
-                state.window.request_redraw();
+                self.last_rendered = Some(id);
+                if let Err(error) = state.render(gpu) {
+                    self.fail(event_loop, error.into());
+                    return;
+                }
+                // nothing to draw while minimized, the next resize wakes us
+                if !state.is_minimized() {
+                    state.window.request_redraw();
+                }
             }
             WindowEvent::Resized(size) => {
-                state.resize(size);
+                state.resize(&gpu.device, size);
+                state.window.request_redraw();
+            }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                state.set_scale_factor(scale_factor);
             }
-            WindowEvent::KeyboardInput { event, .. } => {
-                // input_handler::handleKyeboard(e,state);
-                ()
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(code),
+                        state: ElementState::Pressed,
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } => match code {
+                KeyCode::F1 => state.hud.toggle(),
+                KeyCode::Tab => {
+                    if let Some(compare) = state.compare.as_mut() {
+                        compare.view = compare.view.next();
+                    }
+                }
+                KeyCode::Space => state.clock.toggle_pause(),
+                KeyCode::KeyT => state.tonemap.operator = state.tonemap.operator.next(),
+                KeyCode::Minus => state.tonemap.exposure -= EXPOSURE_STEP,
+                KeyCode::Equal => state.tonemap.exposure += EXPOSURE_STEP,
+                // a copy of the current shader, to keep as a reference
+                KeyCode::KeyN => {
+                    let (shader, geometry) = (state.shader.clone(), state.geometry.clone());
+                    if let Err(error) = self.open_window(event_loop, shader, geometry) {
+                        warn!("can't open another window: {error}");
+                    }
+                }
+                _ => (),
+            },
+            WindowEvent::MouseInput {
+                state: button,
+                button: MouseButton::Left,
+                ..
+            } => {
+                if let Some(compare) = state.compare.as_mut() {
+                    compare.dragging = button.is_pressed();
+                }
             }
             WindowEvent::CursorMoved { position, .. } => {
-                // input_handler::handle_mouse(position, state);
-                ()
+                if let Some(compare) = state.compare.as_mut()
+                    && compare.dragging
+                {
+                    compare.drag_to(position.x, state.size.width);
+                }
             }
             _ => (),
         }
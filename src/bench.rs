@@ -0,0 +1,120 @@
+use crate::gpupipeline::{Renderer, Uniforms};
+use crate::model::Geometry;
+use crate::offline::{Headless, TARGET_FORMAT, target_texture};
+use crate::profiler::Samples;
+use crate::shader;
+use core::error::Error;
+use core::fmt::Write as _;
+use std::path::PathBuf;
+use std::time::Instant;
+use wgpu::{CommandEncoderDescriptor, PollType, TextureViewDescriptor};
+
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    pub shader: Option<PathBuf>,
+    pub width: u32,
+    pub height: u32,
+    /// Frames rendered before measuring, to let caches and clocks settle.
+    pub warmup: u32,
+    pub frames: u32,
+    /// Shader time advance between frames, in seconds.
+    pub time_step: f32,
+    /// Skip hardware adapters and use the fallback software one.
+    pub software: bool,
+}
+
+/// Frame time statistics of a finished benchmark, in milliseconds.
+#[derive(Debug, Clone)]
+pub struct BenchReport {
+    pub adapter: String,
+    pub backend: String,
+    pub samples: Samples,
+}
+
+/// Renders `options.warmup` frames and then times `options.frames` more,
+/// each one submitted and waited on individually.
+///
+/// # Errors
+/// When there is no adapter, the shader can't be read or a frame fails.
+#[expect(clippy::cast_precision_loss, reason = "frame indices fit in f32")]
+pub fn run(options: &BenchOptions) -> Result<BenchReport, Box<dyn Error>> {
+    let headless = pollster::block_on(Headless::new(options.software))?;
+    let Headless { device, queue, .. } = &headless;
+
+    let shader = shader::Source::read(options.shader.as_deref())?;
+    let mut renderer = Renderer::new(device, &shader, TARGET_FORMAT, &Geometry::Quad)?;
+    renderer.resize(device, [options.width, options.height]);
+    let target = target_texture(device, options.width, options.height);
+    let view = target.create_view(&TextureViewDescriptor::default());
+    let resolution = [options.width as f32, options.height as f32];
+
+    let mut samples = Samples::new(options.frames as usize);
+    for frame in 0..options.warmup + options.frames {
+        let uniforms = Uniforms::new(resolution, frame as f32 * options.time_step);
+
+        let start = Instant::now();
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("bench_frame"),
+        });
+        renderer.draw(queue, &mut encoder, &view, &uniforms, None);
+        queue.submit([encoder.finish()]);
+        device.poll(PollType::Wait)?;
+
+        if frame >= options.warmup {
+            samples.push(start.elapsed().as_secs_f32() * 1000.0);
+        }
+    }
+
+    Ok(BenchReport {
+        adapter: headless.adapter_info.name.clone(),
+        backend: headless.adapter_info.backend.to_string(),
+        samples,
+    })
+}
+
+impl BenchReport {
+    /// The report as a single line JSON object.
+    #[must_use]
+    pub fn to_json(&self, options: &BenchOptions) -> String {
+        let shader = options.shader.as_deref().map_or_else(
+            || "null".into(),
+            |path| json_string(&path.to_string_lossy()),
+        );
+        let number =
+            |value: Option<f32>| value.map_or_else(|| "null".into(), |ms| format!("{ms:.4}"));
+
+        format!(
+            "{{\"shader\":{shader},\"adapter\":{},\"backend\":{},\"width\":{},\"height\":{},\
+             \"warmup\":{},\"frames\":{},\"time_step\":{},\"min_ms\":{},\"mean_ms\":{},\
+             \"p95_ms\":{},\"max_ms\":{}}}",
+            json_string(&self.adapter),
+            json_string(&self.backend),
+            options.width,
+            options.height,
+            options.warmup,
+            options.frames,
+            options.time_step,
+            number(self.samples.min()),
+            number(self.samples.mean()),
+            number(self.samples.percentile(95.0)),
+            number(self.samples.max()),
+        )
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for character in value.chars() {
+        match character {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            control if control.is_control() => {
+                let _ = write!(quoted, "\\u{:04x}", u32::from(control));
+            }
+            other => quoted.push(other),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
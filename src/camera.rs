@@ -0,0 +1,54 @@
+use cgmath::{Deg, Matrix4, Point3, Vector3, perspective};
+
+/// cgmath builds OpenGL projections, with depth from -1 to 1. wgpu clips
+/// depth to 0..1.
+#[rustfmt::skip]
+const OPENGL_TO_WGPU: Matrix4<f32> = Matrix4::new(
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 0.5, 0.0,
+    0.0, 0.0, 0.5, 1.0,
+);
+
+/// A perspective camera for the mesh mode, handed to the shader as
+/// `uniforms.view` and `uniforms.projection`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    pub eye: Point3<f32>,
+    pub target: Point3<f32>,
+    pub up: Vector3<f32>,
+    /// Vertical field of view.
+    pub fovy: Deg<f32>,
+    pub znear: f32,
+    pub zfar: f32,
+}
+
+impl Default for Camera {
+    /// Looking at the origin from slightly above, far enough to see all of a
+    /// model fitted into the unit sphere.
+    fn default() -> Camera {
+        Camera {
+            eye: Point3::new(0.0, 1.0, 3.0),
+            target: Point3::new(0.0, 0.0, 0.0),
+            up: Vector3::unit_y(),
+            fovy: Deg(45.0),
+            znear: 0.1,
+            zfar: 100.0,
+        }
+    }
+}
+
+impl Camera {
+    /// World to camera space.
+    #[must_use]
+    pub fn view(&self) -> Matrix4<f32> {
+        Matrix4::look_at_rh(self.eye, self.target, self.up)
+    }
+
+    /// Camera to clip space for a frame of `screen_size` pixels.
+    #[must_use]
+    pub fn projection(&self, screen_size: [f32; 2]) -> Matrix4<f32> {
+        let aspect = screen_size[0] / screen_size[1].max(1.0);
+        OPENGL_TO_WGPU * perspective(self.fovy, aspect, self.znear, self.zfar)
+    }
+}
@@ -0,0 +1,208 @@
+use crate::bench::BenchOptions;
+use crate::downsample::Filter;
+use crate::golden::GoldenOptions;
+use crate::model::Topology;
+use crate::offline::ExportOptions;
+use crate::tonemap::Operator;
+use core::error::Error;
+use core::iter;
+use std::path::PathBuf;
+
+const USAGE: &str = "usage:
+    ray_marching [shader.wgsl ...] [--model <model.obj|.gltf|.glb|cube|sphere|..>]
+        [--vertices <count>] [--topology <points|lines|triangles>]
+        [--tonemap <none|reinhard|aces|agx>] [--exposure <stops>]
+    ray_marching compare <a.wgsl> <b.wgsl>
+    ray_marching export <output.png> [--shader <file.wgsl>] [--size <W>x<H>] [--time <seconds>] [--tile <pixels>]
+        [--ssaa <factor>] [--filter <box|lanczos|mitchell>] [--tonemap <none|reinhard|aces|agx>]
+        [--exposure <stops>]
+    ray_marching bench [--shader <file.wgsl>] [--size <W>x<H>] [--warmup <frames>] [--frames <frames>]
+        [--time-step <seconds>] [--software]
+    ray_marching golden [--shader <file.wgsl>] [--references <dir>] [--size <W>x<H>] [--times <t1,t2,..>]
+        [--tolerance <0-255>] [--max-differing <fraction>] [--bless]";
+
+#[derive(Debug, Clone)]
+pub enum Command {
+    /// A window per shader, or one with the bundled shader when empty.
+    Window {
+        shaders: Vec<PathBuf>,
+        /// Drawn instead of the fullscreen quad.
+        model: Option<PathBuf>,
+        /// Bare vertices drawn instead of the fullscreen quad.
+        vertices: Option<u32>,
+        topology: Topology,
+        tonemap: Operator,
+        /// Stops, see [`crate::tonemap::Tonemap::exposure`].
+        exposure: f32,
+    },
+    /// One window splitting `a` and `b`.
+    Compare {
+        a: PathBuf,
+        b: PathBuf,
+    },
+    Export(ExportOptions),
+    Bench(BenchOptions),
+    Golden(GoldenOptions),
+}
+
+/// Parses the process arguments, without the program name.
+///
+/// # Errors
+/// On unknown commands or flags, and on values that don't parse.
+pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Command, Box<dyn Error>> {
+    let Some(first) = args.next() else {
+        return Ok(Command::Window {
+            shaders: Vec::new(),
+            model: None,
+            vertices: None,
+            topology: Topology::default(),
+            tonemap: Operator::default(),
+            exposure: 0.0,
+        });
+    };
+
+    match first.as_str() {
+        "compare" => match (args.next(), args.next(), args.next()) {
+            (Some(a), Some(b), None) => Ok(Command::Compare {
+                a: PathBuf::from(a),
+                b: PathBuf::from(b),
+            }),
+            _ => Err(format!("compare takes two shaders\n{USAGE}").into()),
+        },
+        "export" => parse_export(args).map(Command::Export),
+        "bench" => parse_bench(args).map(Command::Bench),
+        "golden" => parse_golden(args).map(Command::Golden),
+        _ => parse_window(iter::once(first).chain(args)),
+    }
+}
+
+fn parse_window(mut args: impl Iterator<Item = String>) -> Result<Command, Box<dyn Error>> {
+    let mut shaders = Vec::new();
+    let mut model = None;
+    let mut vertices = None;
+    let mut topology = Topology::default();
+    let mut tonemap = Operator::default();
+    let mut exposure = 0.0;
+
+    while let Some(arg) = args.next() {
+        if !arg.starts_with('-') {
+            shaders.push(PathBuf::from(arg));
+            continue;
+        }
+
+        match arg.as_str() {
+            "--model" => model = Some(PathBuf::from(value(&arg, &mut args)?)),
+            "--vertices" => vertices = Some(value(&arg, &mut args)?.parse()?),
+            "--topology" => topology = value(&arg, &mut args)?.parse()?,
+            "--tonemap" => tonemap = value(&arg, &mut args)?.parse()?,
+            "--exposure" => exposure = value(&arg, &mut args)?.parse()?,
+            _ => return Err(format!("unknown flag `{arg}`\n{USAGE}").into()),
+        }
+    }
+
+    if model.is_some() && vertices.is_some() {
+        return Err(format!("--model and --vertices don't go together\n{USAGE}").into());
+    }
+    Ok(Command::Window {
+        shaders,
+        model,
+        vertices,
+        topology,
+        tonemap,
+        exposure,
+    })
+}
+
+fn parse_export(mut args: impl Iterator<Item = String>) -> Result<ExportOptions, Box<dyn Error>> {
+    let mut options = ExportOptions {
+        output: PathBuf::from(args.next().ok_or(USAGE)?),
+        shader: None,
+        width: 1920,
+        height: 1080,
+        time: 0.0,
+        tile_size: None,
+        ssaa: 1,
+        filter: Filter::default(),
+        tonemap: Operator::default(),
+        exposure: 0.0,
+    };
+
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--shader" => options.shader = Some(PathBuf::from(value(&flag, &mut args)?)),
+            "--size" => (options.width, options.height) = parse_size(&value(&flag, &mut args)?)?,
+            "--time" => options.time = value(&flag, &mut args)?.parse()?,
+            "--tile" => options.tile_size = Some(value(&flag, &mut args)?.parse()?),
+            "--ssaa" => options.ssaa = value(&flag, &mut args)?.parse()?,
+            "--filter" => options.filter = value(&flag, &mut args)?.parse()?,
+            "--tonemap" => options.tonemap = value(&flag, &mut args)?.parse()?,
+            "--exposure" => options.exposure = value(&flag, &mut args)?.parse()?,
+            _ => return Err(format!("unknown flag `{flag}`\n{USAGE}").into()),
+        }
+    }
+
+    Ok(options)
+}
+
+fn parse_bench(mut args: impl Iterator<Item = String>) -> Result<BenchOptions, Box<dyn Error>> {
+    let mut options = BenchOptions {
+        shader: None,
+        width: 1280,
+        height: 720,
+        warmup: 10,
+        frames: 100,
+        time_step: 1.0 / 60.0,
+        software: false,
+    };
+
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--software" => options.software = true,
+            "--shader" => options.shader = Some(PathBuf::from(value(&flag, &mut args)?)),
+            "--size" => (options.width, options.height) = parse_size(&value(&flag, &mut args)?)?,
+            "--warmup" => options.warmup = value(&flag, &mut args)?.parse()?,
+            "--frames" => options.frames = value(&flag, &mut args)?.parse()?,
+            "--time-step" => options.time_step = value(&flag, &mut args)?.parse()?,
+            _ => return Err(format!("unknown flag `{flag}`\n{USAGE}").into()),
+        }
+    }
+
+    Ok(options)
+}
+
+fn parse_golden(mut args: impl Iterator<Item = String>) -> Result<GoldenOptions, Box<dyn Error>> {
+    let mut options = GoldenOptions::default();
+
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--bless" => options.bless = true,
+            "--shader" => options.shader = Some(PathBuf::from(value(&flag, &mut args)?)),
+            "--references" => options.references = PathBuf::from(value(&flag, &mut args)?),
+            "--size" => (options.width, options.height) = parse_size(&value(&flag, &mut args)?)?,
+            "--times" => {
+                options.times = value(&flag, &mut args)?
+                    .split(',')
+                    .map(str::parse)
+                    .collect::<Result<_, _>>()?;
+            }
+            "--tolerance" => options.tolerance = value(&flag, &mut args)?.parse()?,
+            "--max-differing" => options.max_differing = value(&flag, &mut args)?.parse()?,
+            _ => return Err(format!("unknown flag `{flag}`\n{USAGE}").into()),
+        }
+    }
+
+    Ok(options)
+}
+
+/// The value following `flag`.
+fn value(flag: &str, args: &mut impl Iterator<Item = String>) -> Result<String, Box<dyn Error>> {
+    args.next()
+        .ok_or_else(|| format!("missing value for `{flag}`\n{USAGE}").into())
+}
+
+fn parse_size(value: &str) -> Result<(u32, u32), Box<dyn Error>> {
+    let (width, height) = value
+        .split_once('x')
+        .ok_or_else(|| format!("expected <W>x<H>, got `{value}`"))?;
+    Ok((width.parse()?, height.parse()?))
+}
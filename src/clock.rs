@@ -0,0 +1,49 @@
+use std::time::{Duration, Instant};
+
+/// Playback time handed to the shader as `uniforms.time`, which stands still
+/// while paused.
+#[derive(Debug, Clone)]
+pub struct Clock {
+    started: Instant,
+    paused_at: Option<Instant>,
+    /// Time spent paused, excluding the current pause.
+    paused_for: Duration,
+}
+
+impl Clock {
+    #[must_use]
+    pub fn new() -> Clock {
+        Clock {
+            started: Instant::now(),
+            paused_at: None,
+            paused_for: Duration::ZERO,
+        }
+    }
+
+    /// Seconds of playback since the clock was created.
+    #[must_use]
+    pub fn seconds(&self) -> f32 {
+        let now = self.paused_at.unwrap_or_else(Instant::now);
+        now.saturating_duration_since(self.started)
+            .saturating_sub(self.paused_for)
+            .as_secs_f32()
+    }
+
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    pub fn toggle_pause(&mut self) {
+        match self.paused_at.take() {
+            Some(paused_at) => self.paused_for += paused_at.elapsed(),
+            None => self.paused_at = Some(Instant::now()),
+        }
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Clock {
+        Clock::new()
+    }
+}
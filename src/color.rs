@@ -0,0 +1,240 @@
+use core::error::Error;
+use core::str::FromStr;
+
+/// A color in sRGB encoding, as in hex codes, color pickers and images, each
+/// channel from 0 to 1. The encoding spends more of the range on dark tones,
+/// so it can't be blended or lit directly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Srgb(pub [f32; 3]);
+
+/// Linear light in the sRGB primaries, what shaders compute with and what
+/// the GPU expects when writing to an sRGB target.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinearRgb(pub [f32; 3]);
+
+/// Perceptual lightness `l` from 0 to 1, and the green–red `a` and
+/// blue–yellow `b` axes. Equal distances look like equal differences, which
+/// makes it the space to mix colors and build gradients and palettes in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+}
+
+/// [`Oklab`] in polar form: lightness, chroma and hue in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklch {
+    pub l: f32,
+    pub c: f32,
+    pub h: f32,
+}
+
+/// Decodes one sRGB channel to linear light, with the linear segment near
+/// black the standard has instead of a pure power curve.
+#[must_use]
+pub fn srgb_to_linear(channel: f32) -> f32 {
+    if channel <= 0.040_45 {
+        channel / 12.92
+    } else {
+        ((channel + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Encodes one linear channel to sRGB, the inverse of [`srgb_to_linear`].
+#[must_use]
+pub fn linear_to_srgb(channel: f32) -> f32 {
+    if channel <= 0.003_130_8 {
+        channel * 12.92
+    } else {
+        1.055 * channel.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+impl Srgb {
+    /// The color of a `0xRRGGBB` literal.
+    #[must_use]
+    pub fn hex(rgb: u32) -> Srgb {
+        let [_, r, g, b] = rgb.to_be_bytes();
+        Srgb([r, g, b].map(|channel| f32::from(channel) / 255.0))
+    }
+
+    #[must_use]
+    pub fn to_linear(self) -> LinearRgb {
+        LinearRgb(self.0.map(srgb_to_linear))
+    }
+}
+
+impl FromStr for Srgb {
+    type Err = Box<dyn Error>;
+
+    /// Parses `#rrggbb` or the `#rgb` shorthand, with or without the `#`.
+    fn from_str(code: &str) -> Result<Srgb, Self::Err> {
+        let digits = code.strip_prefix('#').unwrap_or(code);
+        if !digits.chars().all(|digit| digit.is_ascii_hexdigit()) {
+            return Err(format!("{code:?} isn't a hex color").into());
+        }
+        let rgb = u32::from_str_radix(digits, 16)?;
+        match digits.len() {
+            6 => Ok(Srgb::hex(rgb)),
+            // each digit doubled, #fa3 is #ffaa33
+            3 => Ok(Srgb::hex(
+                [8, 4, 0]
+                    .map(|shift| ((rgb >> shift) & 0xf) * 0x11)
+                    .into_iter()
+                    .fold(0, |hex, channel| hex << 8 | channel),
+            )),
+            _ => Err(format!("{code:?} isn't #rgb or #rrggbb").into()),
+        }
+    }
+}
+
+impl LinearRgb {
+    #[must_use]
+    pub fn to_srgb(self) -> Srgb {
+        Srgb(self.0.map(linear_to_srgb))
+    }
+
+    /// Through the LMS cone responses, with the matrices of Björn Ottosson's
+    /// Oklab definition.
+    #[must_use]
+    pub fn to_oklab(self) -> Oklab {
+        let [red, green, blue] = self.0;
+        let long = 0.412_221_47 * red + 0.536_332_55 * green + 0.051_445_995 * blue;
+        let medium = 0.211_903_5 * red + 0.680_699_5 * green + 0.107_396_96 * blue;
+        let short = 0.088_302_46 * red + 0.281_718_85 * green + 0.629_978_7 * blue;
+        let [long, medium, short] = [long, medium, short].map(f32::cbrt);
+
+        Oklab {
+            l: 0.210_454_26 * long + 0.793_617_8 * medium - 0.004_072_047 * short,
+            a: 1.977_998_5 * long - 2.428_592_2 * medium + 0.450_593_7 * short,
+            b: 0.025_904_037 * long + 0.782_771_77 * medium - 0.808_675_77 * short,
+        }
+    }
+}
+
+impl Oklab {
+    /// Colors outside the sRGB gamut come out with channels below 0 or
+    /// above 1.
+    #[must_use]
+    pub fn to_linear(self) -> LinearRgb {
+        let long = self.l + 0.396_337_78 * self.a + 0.215_803_76 * self.b;
+        let medium = self.l - 0.105_561_346 * self.a - 0.063_854_17 * self.b;
+        let short = self.l - 0.089_484_18 * self.a - 1.291_485_5 * self.b;
+        let [long, medium, short] = [long, medium, short].map(|cone| cone * cone * cone);
+
+        LinearRgb([
+            4.076_741_7 * long - 3.307_711_6 * medium + 0.230_969_94 * short,
+            -1.268_438 * long + 2.609_757_4 * medium - 0.341_319_38 * short,
+            -0.004_196_086_3 * long - 0.703_418_6 * medium + 1.707_614_7 * short,
+        ])
+    }
+
+    /// The hue of grays is 0.
+    #[must_use]
+    pub fn to_oklch(self) -> Oklch {
+        Oklch {
+            l: self.l,
+            c: self.a.hypot(self.b),
+            h: self.b.atan2(self.a).to_degrees().rem_euclid(360.0),
+        }
+    }
+}
+
+impl Oklch {
+    #[must_use]
+    pub fn to_oklab(self) -> Oklab {
+        let (sin, cos) = self.h.to_radians().sin_cos();
+        Oklab {
+            l: self.l,
+            a: self.c * cos,
+            b: self.c * sin,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: [f32; 3], expected: [f32; 3], tolerance: f32) {
+        assert!(
+            actual
+                .iter()
+                .zip(expected)
+                .all(|(actual, expected)| (actual - expected).abs() <= tolerance),
+            "{actual:?} isn't within {tolerance} of {expected:?}"
+        );
+    }
+
+    #[test]
+    fn srgb_round_trips_through_linear() {
+        for step in 0..=255_u8 {
+            let channel = f32::from(step) / 255.0;
+            assert!((linear_to_srgb(srgb_to_linear(channel)) - channel).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn srgb_is_linear_near_black() {
+        assert!((srgb_to_linear(0.04) - 0.04 / 12.92).abs() < f32::EPSILON);
+        assert!((linear_to_srgb(0.002) - 0.002 * 12.92).abs() < f32::EPSILON);
+        assert!((srgb_to_linear(0.5) - 0.214_041_14).abs() < 1e-6);
+    }
+
+    #[test]
+    fn hex_codes_parse() {
+        let expected = Srgb([1.0, 170.0 / 255.0, 51.0 / 255.0]);
+        assert_eq!("#ffaa33".parse::<Srgb>().ok(), Some(expected));
+        assert_eq!("FFAA33".parse::<Srgb>().ok(), Some(expected));
+        assert_eq!("#fa3".parse::<Srgb>().ok(), Some(expected));
+        assert_eq!(Srgb::hex(0xff_aa_33), expected);
+        assert!("#ffaa3".parse::<Srgb>().is_err());
+        assert!("#ggaa33".parse::<Srgb>().is_err());
+        assert!("+fa3".parse::<Srgb>().is_err());
+    }
+
+    #[test]
+    fn oklab_matches_reference_values() {
+        let white = LinearRgb([1.0, 1.0, 1.0]).to_oklab();
+        assert_close([white.l, white.a, white.b], [1.0, 0.0, 0.0], 1e-4);
+
+        let red = LinearRgb([1.0, 0.0, 0.0]).to_oklab();
+        assert_close(
+            [red.l, red.a, red.b],
+            [0.627_955, 0.224_863, 0.125_846],
+            1e-4,
+        );
+
+        let blue = LinearRgb([0.0, 0.0, 1.0]).to_oklab();
+        assert_close(
+            [blue.l, blue.a, blue.b],
+            [0.452_014, -0.032_457, -0.311_528],
+            1e-4,
+        );
+    }
+
+    #[test]
+    fn oklab_round_trips() {
+        for rgb in [
+            [0.2, 0.5, 0.9],
+            [1.0, 0.0, 0.0],
+            [0.0, 0.0, 0.0],
+            [0.01, 0.7, 0.3],
+        ] {
+            assert_close(LinearRgb(rgb).to_oklab().to_linear().0, rgb, 1e-4);
+        }
+    }
+
+    #[test]
+    fn oklch_is_oklab_in_polar_form() {
+        let lab = LinearRgb([0.2, 0.5, 0.9]).to_oklab();
+        let lch = lab.to_oklch();
+        assert!((0.0..360.0).contains(&lch.h));
+        let back = lch.to_oklab();
+        assert_close([back.l, back.a, back.b], [lab.l, lab.a, lab.b], 1e-6);
+
+        let gray = LinearRgb([0.5, 0.5, 0.5]).to_oklab().to_oklch();
+        assert!(gray.c < 1e-4);
+    }
+}
@@ -0,0 +1,309 @@
+use crate::error::SetupError;
+use crate::gpupipeline::{Renderer, Uniforms};
+use crate::model::Geometry;
+use crate::shader;
+use bytemuck::Zeroable as _;
+use wgpu::util::DeviceExt as _;
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingResource, BindingType, Buffer, BufferBindingType, BufferUsages,
+    ColorTargetState, ColorWrites, CommandEncoder, Device, Extent3d, FragmentState, LoadOp,
+    MultisampleState, Operations, PipelineCompilationOptions, PipelineLayoutDescriptor,
+    PrimitiveState, Queue, RenderPassColorAttachment, RenderPassDescriptor,
+    RenderPassTimestampWrites, RenderPipeline, RenderPipelineDescriptor, ShaderModule,
+    ShaderStages, StoreOp, TextureDescriptor, TextureDimension, TextureFormat, TextureSampleType,
+    TextureUsages, TextureView, TextureViewDescriptor, TextureViewDimension, VertexState,
+    include_wgsl,
+};
+
+/// How much the difference view amplifies the per channel difference.
+const DIFFERENCE_GAIN: f32 = 10.0;
+
+/// What the comparison shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum View {
+    /// A left of the divider and B right of it.
+    #[default]
+    Split,
+    A,
+    B,
+    /// The absolute difference of A and B, amplified.
+    Difference,
+}
+
+impl View {
+    #[must_use]
+    pub fn next(self) -> View {
+        match self {
+            View::Split => View::A,
+            View::A => View::B,
+            View::B => View::Difference,
+            View::Difference => View::Split,
+        }
+    }
+
+    /// The value `compare.wgsl` switches on.
+    fn index(self) -> u32 {
+        match self {
+            View::Split => 0,
+            View::A => 1,
+            View::B => 2,
+            View::Difference => 3,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct CompareUniforms {
+    split: f32,
+    view: u32,
+    gain: f32,
+    line_width: f32,
+}
+
+/// A second shader drawn with the same uniforms as the window's own, and the
+/// pass that puts both on screen.
+///
+/// Each shader renders into its own window sized target. The window's
+/// renderer draws A into [`Compare::a`], [`Compare::draw`] draws B and
+/// combines both according to [`Compare::view`].
+#[derive(Debug)]
+pub struct Compare {
+    pub b: Renderer,
+    pub b_shader: shader::Source,
+    /// Divider position as a fraction of the window width.
+    pub split: f32,
+    pub view: View,
+    /// Whether the divider follows the cursor.
+    pub dragging: bool,
+    bind_group: BindGroup,
+    bind_group_layout: BindGroupLayout,
+    pipeline: RenderPipeline,
+    targets: [TextureView; 2],
+    uniforms_buffer: Buffer,
+}
+
+impl Compare {
+    /// `format` and `geometry` are the ones the window's renderer draws
+    /// with, and `size` the window size in pixels. The result is written
+    /// as `format` too, through its sRGB view if it has one.
+    ///
+    /// # Errors
+    /// When `b_shader` can't draw `geometry`, see [`Renderer::new`].
+    pub fn new(
+        device: &Device,
+        b_shader: shader::Source,
+        format: TextureFormat,
+        geometry: &Geometry,
+        size: [u32; 2],
+    ) -> Result<Compare, SetupError> {
+        let mut b = Renderer::new(device, &b_shader, format, geometry)?;
+        b.resize(device, size);
+        let shader = device.create_shader_module(include_wgsl!("compare.wgsl"));
+
+        let uniforms_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("compare_uniforms_buffer"),
+            contents: bytemuck::bytes_of(&CompareUniforms::zeroed()),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let texture_entry = |binding| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Texture {
+                sample_type: TextureSampleType::Float { filterable: false },
+                view_dimension: TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("compare_bindgroup_layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                texture_entry(1),
+                texture_entry(2),
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("compare_pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = pipeline(device, &shader, &pipeline_layout, format.add_srgb_suffix());
+
+        let targets = targets(device, format, size);
+        let bind_group = bind_group(device, &bind_group_layout, &uniforms_buffer, &targets);
+
+        Ok(Compare {
+            b,
+            b_shader,
+            split: 0.5,
+            view: View::default(),
+            dragging: false,
+            bind_group,
+            bind_group_layout,
+            pipeline,
+            targets,
+            uniforms_buffer,
+        })
+    }
+
+    /// The target the window's own shader, A, has to be drawn into.
+    #[must_use]
+    pub fn a(&self) -> &TextureView {
+        &self.targets[0]
+    }
+
+    /// Recreates the targets for a window of `size` pixels.
+    pub fn resize(&mut self, device: &Device, format: TextureFormat, size: [u32; 2]) {
+        self.b.resize(device, size);
+        self.targets = targets(device, format, size);
+        self.bind_group = bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.uniforms_buffer,
+            &self.targets,
+        );
+    }
+
+    /// Moves the divider under the cursor at `x` in a window `width` wide.
+    #[expect(clippy::cast_possible_truncation, reason = "a fraction")]
+    pub fn drag_to(&mut self, x: f64, width: u32) {
+        if width > 0 {
+            self.split = (x / f64::from(width)).clamp(0.0, 1.0) as f32;
+        }
+    }
+
+    /// Draws B with `uniforms` and records the pass combining it with A into
+    /// `view`. `uniforms` decides the size of the window and the width of the
+    /// divider.
+    pub fn draw(
+        &self,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        view: &TextureView,
+        uniforms: &Uniforms,
+        timestamp_writes: Option<RenderPassTimestampWrites<'_>>,
+    ) {
+        self.b
+            .draw(queue, encoder, &self.targets[1], uniforms, None);
+
+        let compare = CompareUniforms {
+            split: self.split * uniforms.screen_size()[0],
+            view: self.view.index(),
+            gain: DIFFERENCE_GAIN,
+            line_width: uniforms.scale_factor(),
+        };
+        queue.write_buffer(&self.uniforms_buffer, 0, bytemuck::bytes_of(&compare));
+
+        let mut renderpass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("compare"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Load,
+                    store: StoreOp::Store,
+                },
+            })],
+            timestamp_writes,
+            ..Default::default()
+        });
+        renderpass.set_pipeline(&self.pipeline);
+        renderpass.set_bind_group(0, &self.bind_group, &[]);
+        renderpass.draw(0..3, 0..1);
+    }
+}
+
+/// Views of the A and B targets, at least one pixel in size.
+fn targets(device: &Device, format: TextureFormat, size: [u32; 2]) -> [TextureView; 2] {
+    ["compare_a", "compare_b"].map(|label| {
+        device
+            .create_texture(&TextureDescriptor {
+                label: Some(label),
+                size: Extent3d {
+                    width: size[0].max(1),
+                    height: size[1].max(1),
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format,
+                usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            })
+            .create_view(&TextureViewDescriptor::default())
+    })
+}
+
+fn bind_group(
+    device: &Device,
+    layout: &BindGroupLayout,
+    uniforms_buffer: &Buffer,
+    targets: &[TextureView; 2],
+) -> BindGroup {
+    device.create_bind_group(&BindGroupDescriptor {
+        label: Some("compare_bind_group"),
+        layout,
+        entries: &[
+            BindGroupEntry {
+                binding: 0,
+                resource: uniforms_buffer.as_entire_binding(),
+            },
+            BindGroupEntry {
+                binding: 1,
+                resource: BindingResource::TextureView(&targets[0]),
+            },
+            BindGroupEntry {
+                binding: 2,
+                resource: BindingResource::TextureView(&targets[1]),
+            },
+        ],
+    })
+}
+
+fn pipeline(
+    device: &Device,
+    shader: &ShaderModule,
+    layout: &wgpu::PipelineLayout,
+    format: TextureFormat,
+) -> RenderPipeline {
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("compare_pipeline"),
+        layout: Some(layout),
+        vertex: VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            buffers: &[],
+            compilation_options: PipelineCompilationOptions::default(),
+        },
+        fragment: Some(FragmentState {
+            module: shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(ColorTargetState {
+                format,
+                blend: None,
+                write_mask: ColorWrites::ALL,
+            })],
+            compilation_options: PipelineCompilationOptions::default(),
+        }),
+        primitive: PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
@@ -0,0 +1,45 @@
+struct Compare {
+    // Divider position, in framebuffer pixels from the left.
+    split: f32,
+    // 0 split, 1 only A, 2 only B, 3 amplified difference.
+    view: u32,
+    gain: f32,
+    line_width: f32,
+}
+
+@group(0) @binding(0) var<uniform> compare: Compare;
+@group(0) @binding(1) var a: texture_2d<f32>;
+@group(0) @binding(2) var b: texture_2d<f32>;
+
+// One triangle covering the whole target, no vertex buffer.
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let texel = vec2<i32>(position.xy);
+    let left = textureLoad(a, texel, 0);
+    let right = textureLoad(b, texel, 0);
+
+    switch compare.view {
+        case 1u: {
+            return left;
+        }
+        case 2u: {
+            return right;
+        }
+        case 3u: {
+            let difference = min(abs(left.rgb - right.rgb) * compare.gain, vec3<f32>(1.0));
+            return vec4<f32>(difference, 1.0);
+        }
+        default: {
+            if abs(position.x - compare.split) < compare.line_width {
+                return vec4<f32>(1.0);
+            }
+            return select(right, left, position.x < compare.split);
+        }
+    }
+}
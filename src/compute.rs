@@ -0,0 +1,185 @@
+use crate::storage;
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingResource, BindingType, CommandEncoder, ComputePassDescriptor,
+    ComputePipeline, ComputePipelineDescriptor, Device, Extent3d, PipelineCompilationOptions,
+    PipelineLayoutDescriptor, ShaderModule, ShaderStages, StorageTextureAccess, TextureDescriptor,
+    TextureDimension, TextureFormat, TextureSampleType, TextureUsages, TextureViewDescriptor,
+    TextureViewDimension,
+};
+
+/// Name a compute entry point needs to be run before the render pass.
+pub const ENTRY_POINT: &str = "cs_main";
+
+/// Group of the output texture, at binding 0.
+pub const GROUP: u32 = 1;
+
+/// Format of the storage texture at `@group(1) @binding(0)`, declared in
+/// WGSL as `texture_storage_2d<rgba16float, write>`.
+pub const OUTPUT_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+/// A compute entry point dispatched over the whole target, one invocation per
+/// pixel, writing to a storage texture.
+///
+/// The fragment stage of the same shader sees that texture as a
+/// `texture_2d<f32>` at `@group(1) @binding(0)`, through
+/// [`Compute::output_bind_group`].
+#[derive(Debug)]
+pub struct Compute {
+    /// Layout of the fragment stage's view of the output.
+    pub output_layout: BindGroupLayout,
+    pub output_bind_group: BindGroup,
+    pipeline: ComputePipeline,
+    size: [u32; 2],
+    storage_bind_group: BindGroup,
+    storage_layout: BindGroupLayout,
+    workgroup_size: [u32; 3],
+}
+
+impl Compute {
+    /// `uniforms_layout` is group 0, shared with the render pipeline, and
+    /// `buffers_layout` the storage buffers at group 2, if any.
+    /// `output_visibility` are the render stages that read the output.
+    #[must_use]
+    pub fn new(
+        device: &Device,
+        shader: &ShaderModule,
+        uniforms_layout: &BindGroupLayout,
+        buffers_layout: Option<&BindGroupLayout>,
+        output_visibility: ShaderStages,
+        workgroup_size: [u32; 3],
+    ) -> Compute {
+        let storage_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("compute_storage_layout"),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::COMPUTE,
+                ty: BindingType::StorageTexture {
+                    access: StorageTextureAccess::WriteOnly,
+                    format: OUTPUT_FORMAT,
+                    view_dimension: TextureViewDimension::D2,
+                },
+                count: None,
+            }],
+        });
+        let output_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("compute_output_layout"),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: output_visibility,
+                ty: BindingType::Texture {
+                    sample_type: TextureSampleType::Float { filterable: true },
+                    view_dimension: TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+        });
+
+        let mut bind_group_layouts = vec![uniforms_layout, &storage_layout];
+        bind_group_layouts.extend(buffers_layout);
+        let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("compute_pipeline_layout"),
+            bind_group_layouts: &bind_group_layouts,
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("compute_pipeline"),
+            layout: Some(&layout),
+            module: shader,
+            entry_point: Some(ENTRY_POINT),
+            compilation_options: PipelineCompilationOptions::default(),
+            cache: None,
+        });
+
+        let size = [1, 1];
+        let (storage_bind_group, output_bind_group) =
+            output(device, &storage_layout, &output_layout, size);
+
+        Compute {
+            output_layout,
+            output_bind_group,
+            pipeline,
+            size,
+            storage_bind_group,
+            storage_layout,
+            workgroup_size,
+        }
+    }
+
+    /// Reallocates the output for targets of `size` pixels.
+    pub fn resize(&mut self, device: &Device, size: [u32; 2]) {
+        let size = size.map(|side| side.max(1));
+        if size == self.size {
+            return;
+        }
+
+        (self.storage_bind_group, self.output_bind_group) =
+            output(device, &self.storage_layout, &self.output_layout, size);
+        self.size = size;
+    }
+
+    /// Records the compute pass, with `uniforms` bound at group 0 and
+    /// `buffers` at group 2.
+    pub fn dispatch(
+        &self,
+        encoder: &mut CommandEncoder,
+        uniforms: &BindGroup,
+        buffers: Option<&BindGroup>,
+    ) {
+        let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: Some("compute"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, uniforms, &[]);
+        pass.set_bind_group(GROUP, &self.storage_bind_group, &[]);
+        if let Some(buffers) = buffers {
+            pass.set_bind_group(storage::GROUP, buffers, &[]);
+        }
+        pass.dispatch_workgroups(
+            self.size[0].div_ceil(self.workgroup_size[0].max(1)),
+            self.size[1].div_ceil(self.workgroup_size[1].max(1)),
+            1,
+        );
+    }
+}
+
+/// A `size` output texture, bound for writing and for reading.
+fn output(
+    device: &Device,
+    storage_layout: &BindGroupLayout,
+    output_layout: &BindGroupLayout,
+    size: [u32; 2],
+) -> (BindGroup, BindGroup) {
+    let texture = device.create_texture(&TextureDescriptor {
+        label: Some("compute_output"),
+        size: Extent3d {
+            width: size[0],
+            height: size[1],
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format: OUTPUT_FORMAT,
+        usage: TextureUsages::STORAGE_BINDING | TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&TextureViewDescriptor::default());
+
+    let bind_group = |label, layout| {
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some(label),
+            layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: BindingResource::TextureView(&view),
+            }],
+        })
+    };
+    (
+        bind_group("compute_storage_bind_group", storage_layout),
+        bind_group("compute_output_bind_group", output_layout),
+    )
+}
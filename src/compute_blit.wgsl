@@ -0,0 +1,20 @@
+// Shows the output of a shader that only has a compute entry point.
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec3<f32>,
+}
+
+@group(1) @binding(0) var computed: texture_2d<f32>;
+
+@vertex
+fn vs_main(in: VertexInput) -> @builtin(position) vec4<f32> {
+    return vec4<f32>(in.position, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    // the compute pass covers the same pixels as the target, a tile of an
+    // export included
+    return textureLoad(computed, vec2<i32>(position.xy), 0);
+}
@@ -11,11 +11,13 @@ pub trait Configuration {
         adapter: &Adapter,
         surface: &Surface,
         size: &PhysicalSize<u32>,
-    ) -> SurfaceConfiguration;
+    ) -> Option<SurfaceConfiguration>;
     fn primitives() -> PrimitiveState;
     fn multisample() -> MultisampleState;
-    fn device_dec(limits: Limits) -> DeviceDescriptor<'static>;
+    fn optional_features() -> Features;
+    fn device_dec(limits: Limits, features: Features) -> DeviceDescriptor<'static>;
     fn adapter<'a>(surface: &'a Surface) -> RequestAdapterOptions<'a, 'a>;
+    fn headless_adapter(force_fallback_adapter: bool) -> RequestAdapterOptions<'static, 'static>;
 }
 
 impl Configuration for DefaultConf {
@@ -39,6 +41,14 @@ impl Configuration for DefaultConf {
         }
     }
 
+    fn headless_adapter(force_fallback_adapter: bool) -> RequestAdapterOptions<'static, 'static> {
+        RequestAdapterOptions {
+            power_preference: PowerPreference::HighPerformance,
+            compatible_surface: None,
+            force_fallback_adapter,
+        }
+    }
+
     fn multisample() -> MultisampleState {
         MultisampleState {
             count: 1,
@@ -47,10 +57,14 @@ impl Configuration for DefaultConf {
         }
     }
 
-    fn device_dec(limits: Limits) -> DeviceDescriptor<'static> {
+    fn optional_features() -> Features {
+        Features::TIMESTAMP_QUERY
+    }
+
+    fn device_dec(limits: Limits, features: Features) -> DeviceDescriptor<'static> {
         DeviceDescriptor {
             label: None,
-            required_features: Features::empty(),
+            required_features: features,
             required_limits: limits,
             memory_hints: MemoryHints::Performance,
             trace: Trace::Off,
@@ -61,33 +75,25 @@ impl Configuration for DefaultConf {
         adapter: &Adapter,
         surface: &Surface,
         size: &PhysicalSize<u32>,
-    ) -> SurfaceConfiguration {
+    ) -> Option<SurfaceConfiguration> {
         let surface_caps = surface.get_capabilities(adapter);
 
-        #[expect(clippy::unwrap_used, reason = "unrecoverable")]
         let format = surface_caps
             .formats
             .iter()
             .copied()
             .find(TextureFormat::is_srgb)
-            .unwrap_or(*surface_caps.formats.first().unwrap());
+            .or_else(|| surface_caps.formats.first().copied())?;
 
-        #[expect(clippy::expect_used, reason = "unrecoverable")]
-        SurfaceConfiguration {
+        Some(SurfaceConfiguration {
             format,
             width: size.width,
             height: size.height,
-            present_mode: *surface_caps
-                .present_modes
-                .first()
-                .expect("no surface present modes available"),
-            alpha_mode: *surface_caps
-                .alpha_modes
-                .first()
-                .expect("no alpha_modes availables"),
+            present_mode: *surface_caps.present_modes.first()?,
+            alpha_mode: *surface_caps.alpha_modes.first()?,
             usage: TextureUsages::RENDER_ATTACHMENT,
             desired_maximum_frame_latency: 2,
             view_formats: vec![],
-        }
+        })
     }
 }
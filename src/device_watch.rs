@@ -0,0 +1,47 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{error, warn};
+use wgpu::{Device, DeviceLostReason};
+
+/// Notices when a device stops working, from its lost callback or an
+/// internal error, so the owner can rebuild everything on a new one.
+///
+/// Also keeps validation errors from panicking, which is what wgpu does with
+/// uncaptured errors by default.
+#[derive(Debug, Clone)]
+pub struct DeviceWatch {
+    lost: Arc<AtomicBool>,
+}
+
+impl DeviceWatch {
+    #[must_use]
+    pub fn new(device: &Device) -> DeviceWatch {
+        let lost = Arc::new(AtomicBool::new(false));
+
+        let flag = Arc::clone(&lost);
+        device.set_device_lost_callback(move |reason, message| {
+            // `Destroyed` is us dropping the device, not a failure
+            if reason == DeviceLostReason::Unknown {
+                error!("device lost: {message}");
+                flag.store(true, Ordering::Release);
+            }
+        });
+
+        let flag = Arc::clone(&lost);
+        device.on_uncaptured_error(Box::new(move |gpu_error| {
+            if let wgpu::Error::Internal { .. } = gpu_error {
+                error!("internal GPU error, treating the device as lost: {gpu_error}");
+                flag.store(true, Ordering::Release);
+            } else {
+                warn!("uncaptured GPU error: {gpu_error}");
+            }
+        }));
+
+        DeviceWatch { lost }
+    }
+
+    #[must_use]
+    pub fn is_lost(&self) -> bool {
+        self.lost.load(Ordering::Acquire)
+    }
+}
@@ -0,0 +1,273 @@
+use crate::color;
+use core::error::Error;
+use core::f32::consts::PI;
+use core::str::FromStr;
+use image::RgbaImage;
+
+const CHANNELS: usize = 4;
+
+/// Reconstruction filter used to shrink a supersampled frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Filter {
+    Box,
+    #[default]
+    Lanczos,
+    Mitchell,
+}
+
+impl Filter {
+    /// Half width of the kernel, in output pixels.
+    fn radius(self) -> f32 {
+        match self {
+            Filter::Box => 0.5,
+            Filter::Lanczos => 3.0,
+            Filter::Mitchell => 2.0,
+        }
+    }
+
+    fn weight(self, x: f32) -> f32 {
+        let x = x.abs();
+        match self {
+            Filter::Box => {
+                if x <= 0.5 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Filter::Lanczos => {
+                if x < f32::EPSILON {
+                    1.0
+                } else if x < 3.0 {
+                    let px = PI * x;
+                    3.0 * px.sin() * (px / 3.0).sin() / (px * px)
+                } else {
+                    0.0
+                }
+            }
+            // Mitchell-Netravali with B = C = 1/3.
+            Filter::Mitchell => {
+                const B: f32 = 1.0 / 3.0;
+                const C: f32 = 1.0 / 3.0;
+                if x < 1.0 {
+                    ((12.0 - 9.0 * B - 6.0 * C) * x.powi(3)
+                        + (-18.0 + 12.0 * B + 6.0 * C) * x.powi(2)
+                        + (6.0 - 2.0 * B))
+                        / 6.0
+                } else if x < 2.0 {
+                    ((-B - 6.0 * C) * x.powi(3)
+                        + (6.0 * B + 30.0 * C) * x.powi(2)
+                        + (-12.0 * B - 48.0 * C) * x
+                        + (8.0 * B + 24.0 * C))
+                        / 6.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = Box<dyn Error>;
+
+    fn from_str(name: &str) -> Result<Filter, Self::Err> {
+        match name {
+            "box" => Ok(Filter::Box),
+            "lanczos" => Ok(Filter::Lanczos),
+            "mitchell" => Ok(Filter::Mitchell),
+            _ => Err(format!("unknown filter `{name}`, expected box, lanczos or mitchell").into()),
+        }
+    }
+}
+
+/// Shrinks `image` by `factor` on both axes.
+///
+/// Filtering happens on linear light, so the sRGB encoded pixels are decoded
+/// first and encoded again at the end.
+#[must_use]
+pub fn downsample(image: &RgbaImage, factor: u32, filter: Filter) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    let (out_width, out_height) = (width / factor, height / factor);
+
+    let linear: Vec<f32> = image
+        .pixels()
+        .flat_map(|pixel| {
+            let [r, g, b, a] = pixel.0;
+            [
+                byte_to_linear(r),
+                byte_to_linear(g),
+                byte_to_linear(b),
+                f32::from(a) / 255.0,
+            ]
+        })
+        .collect();
+
+    let horizontal = resample_rows(&linear, width, height, out_width, factor, filter);
+    let transposed = transpose(&horizontal, out_width, height);
+    let vertical = resample_rows(&transposed, height, out_width, out_height, factor, filter);
+    let result = transpose(&vertical, out_height, out_width);
+
+    let mut output = RgbaImage::new(out_width, out_height);
+    for (index, (byte, value)) in output.iter_mut().zip(result).enumerate() {
+        *byte = if index % CHANNELS == CHANNELS - 1 {
+            unit_to_byte(value)
+        } else {
+            linear_to_byte(value)
+        };
+    }
+    output
+}
+
+/// Filters every row of a `width`x`rows` image down to `out_width` columns.
+#[expect(clippy::cast_precision_loss, reason = "image sizes fit in f32")]
+#[expect(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    reason = "kernel bounds are clamped to the row"
+)]
+fn resample_rows(
+    source: &[f32],
+    width: u32,
+    rows: u32,
+    out_width: u32,
+    factor: u32,
+    filter: Filter,
+) -> Vec<f32> {
+    let scale = factor as f32;
+    let support = filter.radius() * scale;
+
+    // Weights only depend on the column, so they are shared by every row.
+    let kernels: Vec<(usize, Vec<f32>)> = (0..out_width)
+        .map(|x| {
+            let center = (x as f32 + 0.5) * scale;
+            let first = (center - support).floor().max(0.0) as usize;
+            let last = ((center + support).ceil() as usize).min(width as usize);
+            let mut weights: Vec<f32> = (first..last)
+                .map(|i| filter.weight((i as f32 + 0.5 - center) / scale))
+                .collect();
+            let total: f32 = weights.iter().sum();
+            for weight in &mut weights {
+                *weight /= total;
+            }
+            (first, weights)
+        })
+        .collect();
+
+    let mut out = vec![0.0; out_width as usize * rows as usize * CHANNELS];
+    for (row, out_row) in out
+        .chunks_exact_mut(out_width as usize * CHANNELS)
+        .enumerate()
+    {
+        let row_start = row * width as usize * CHANNELS;
+        for ((first, weights), out_pixel) in
+            kernels.iter().zip(out_row.as_chunks_mut::<CHANNELS>().0)
+        {
+            for (offset, weight) in weights.iter().enumerate() {
+                let src = row_start + (first + offset) * CHANNELS;
+                for (channel, value) in out_pixel.iter_mut().enumerate() {
+                    *value += source[src + channel] * weight;
+                }
+            }
+        }
+    }
+    out
+}
+
+fn transpose(source: &[f32], width: u32, height: u32) -> Vec<f32> {
+    let (width, height) = (width as usize, height as usize);
+    let mut out = vec![0.0; source.len()];
+    for y in 0..height {
+        for x in 0..width {
+            let src = (y * width + x) * CHANNELS;
+            let dst = (x * height + y) * CHANNELS;
+            out[dst..dst + CHANNELS].copy_from_slice(&source[src..src + CHANNELS]);
+        }
+    }
+    out
+}
+
+/// [`color::srgb_to_linear`] of a byte.
+fn byte_to_linear(value: u8) -> f32 {
+    color::srgb_to_linear(f32::from(value) / 255.0)
+}
+
+/// [`color::linear_to_srgb`] as a byte, out of range values clamped.
+fn linear_to_byte(value: f32) -> u8 {
+    unit_to_byte(color::linear_to_srgb(value.clamp(0.0, 1.0)))
+}
+
+#[expect(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    reason = "the value is clamped to 0..=255 first"
+)]
+fn unit_to_byte(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn kernels_peak_at_the_center() {
+        assert!((Filter::Box.weight(0.0) - 1.0).abs() < f32::EPSILON);
+        assert!((Filter::Lanczos.weight(0.0) - 1.0).abs() < f32::EPSILON);
+        assert!((Filter::Mitchell.weight(0.0) - 8.0 / 9.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn kernels_vanish_past_their_radius() {
+        for filter in [Filter::Box, Filter::Lanczos, Filter::Mitchell] {
+            assert!(filter.weight(filter.radius() + 0.01).abs() < f32::EPSILON);
+            assert!(filter.weight(-filter.radius() - 0.01).abs() < f32::EPSILON);
+        }
+        // Lanczos crosses zero at every integer
+        for x in [1.0, 2.0] {
+            assert!(Filter::Lanczos.weight(x).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn mitchell_weights_sum_to_one() {
+        // the kernel sampled at integer spacing is a partition of unity
+        for offset in [0.0, 0.25, 0.5, 0.75] {
+            let total: f32 = [-2.0, -1.0, 0.0, 1.0, 2.0]
+                .into_iter()
+                .map(|x| Filter::Mitchell.weight(x + offset))
+                .sum();
+            assert!((total - 1.0).abs() < 1e-5, "{total} at {offset}");
+        }
+    }
+
+    #[test]
+    fn flat_colors_survive_the_linear_round_trip() {
+        for filter in [Filter::Box, Filter::Lanczos, Filter::Mitchell] {
+            for value in [0, 1, 60, 128, 200, 255] {
+                let image = RgbaImage::from_pixel(8, 8, Rgba([value, value / 2, 255 - value, 128]));
+                let small = downsample(&image, 2, filter);
+                assert_eq!(small.dimensions(), (4, 4));
+                assert!(
+                    small.pixels().all(|pixel| *pixel == image[(0, 0)]),
+                    "{filter:?} changed {value}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn averaging_happens_in_linear_light() {
+        // black and white averaged as light is 0.5 linear, which sRGB encodes
+        // as 188 rather than the 128 of averaging the bytes
+        let image = RgbaImage::from_fn(2, 2, |x, y| {
+            let value = if (x + y) % 2 == 0 { 0 } else { 255 };
+            Rgba([value, value, value, 255])
+        });
+        assert_eq!(
+            downsample(&image, 2, Filter::Box)[(0, 0)],
+            Rgba([188, 188, 188, 255])
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use core::error::Error;
+use core::fmt;
+use std::io;
+use std::path::PathBuf;
+use wgpu::{CreateSurfaceError, RequestAdapterError, RequestDeviceError};
+use winit::error::OsError;
+
+/// Everything that can go wrong while bringing up a window, a device or a
+/// shader, before the first frame is drawn.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum SetupError {
+    Window(OsError),
+    CreateSurface(CreateSurfaceError),
+    NoAdapter(RequestAdapterError),
+    NoDevice(RequestDeviceError),
+    /// The adapter can't present to the window's surface in any format.
+    SurfaceUnsupported,
+    ReadShader {
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// `diagnostics` holds the compiler output, with source snippets.
+    CompileShader {
+        name: String,
+        diagnostics: String,
+    },
+    /// The initial contents of a storage buffer.
+    ReadBuffer {
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// A storage buffer whose size can't be worked out, or doesn't fit its
+    /// initial contents.
+    BufferSize {
+        name: String,
+        reason: String,
+    },
+    /// A shader whose `vs_main` inputs aren't what the vertex buffer holds.
+    VertexInput {
+        name: String,
+        reason: String,
+    },
+    /// A uniform buffer declared differently from what is uploaded to it.
+    UniformLayout {
+        name: String,
+        reason: String,
+    },
+    /// A resource declared at a binding nothing is bound to.
+    Binding {
+        name: String,
+        reason: String,
+    },
+    /// A model file that can't be read or has nothing to draw.
+    LoadModel {
+        path: PathBuf,
+        source: Box<dyn Error + Send + Sync>,
+    },
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetupError::Window(error) => write!(formatter, "can't create a window: {error}"),
+            SetupError::CreateSurface(error) => {
+                write!(formatter, "can't render to the window: {error}")
+            }
+            SetupError::NoAdapter(error) => write!(formatter, "no usable GPU adapter: {error}"),
+            SetupError::NoDevice(error) => {
+                write!(formatter, "the adapter refused to give a device: {error}")
+            }
+            SetupError::SurfaceUnsupported => {
+                write!(formatter, "the adapter can't present to this window")
+            }
+            SetupError::ReadShader { path, source } | SetupError::ReadBuffer { path, source } => {
+                write!(formatter, "can't read {}: {source}", path.display())
+            }
+            SetupError::CompileShader { name, diagnostics } => {
+                write!(formatter, "{name} doesn't compile:\n{diagnostics}")
+            }
+            SetupError::BufferSize { name, reason } => {
+                write!(formatter, "can't size the {name} storage buffer: {reason}")
+            }
+            SetupError::VertexInput { name, reason } => {
+                write!(formatter, "{name} can't draw these vertices: {reason}")
+            }
+            SetupError::UniformLayout { name, reason } => {
+                write!(
+                    formatter,
+                    "{name} doesn't declare the uniforms as uploaded: {reason}"
+                )
+            }
+            SetupError::Binding { name, reason } => {
+                write!(formatter, "{name} can't be bound: {reason}")
+            }
+            SetupError::LoadModel { path, source } => {
+                write!(formatter, "can't load {}: {source}", path.display())
+            }
+        }
+    }
+}
+
+impl Error for SetupError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SetupError::Window(error) => Some(error),
+            SetupError::CreateSurface(error) => Some(error),
+            SetupError::NoAdapter(error) => Some(error),
+            SetupError::NoDevice(error) => Some(error),
+            SetupError::ReadShader { source, .. } | SetupError::ReadBuffer { source, .. } => {
+                Some(source)
+            }
+            SetupError::LoadModel { source, .. } => Some(source.as_ref()),
+            SetupError::SurfaceUnsupported
+            | SetupError::CompileShader { .. }
+            | SetupError::BufferSize { .. }
+            | SetupError::VertexInput { .. }
+            | SetupError::UniformLayout { .. }
+            | SetupError::Binding { .. } => None,
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use crate::gpupipeline::Renderer;
+use crate::model::Geometry;
+use crate::offline::{Headless, TARGET_FORMAT, render_tiled};
+use crate::shader;
+use crate::tonemap::{self, Tonemap};
+use core::error::Error;
+use image::{Rgba, RgbaImage};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+const TILE_SIZE: u32 = 2048;
+
+#[derive(Debug, Clone)]
+pub struct GoldenOptions {
+    pub shader: Option<PathBuf>,
+    /// Directory holding the reference PNGs, and where diffs are written.
+    pub references: PathBuf,
+    pub width: u32,
+    pub height: u32,
+    /// Shader times at which a frame is compared.
+    pub times: Vec<f32>,
+    /// Largest per channel difference, out of 255, that still counts as equal.
+    pub tolerance: u8,
+    /// Fraction of pixels allowed to differ before a frame fails.
+    pub max_differing: f32,
+    /// Overwrite the references with the current renders instead of comparing.
+    pub bless: bool,
+}
+
+impl Default for GoldenOptions {
+    fn default() -> GoldenOptions {
+        GoldenOptions {
+            shader: None,
+            references: PathBuf::from("tests/golden"),
+            width: 256,
+            height: 144,
+            times: vec![0.0, 1.0, 2.5],
+            tolerance: 2,
+            max_differing: 0.001,
+            bless: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Passed {
+        differing: usize,
+    },
+    Blessed,
+    /// There is no reference yet, run again with `bless`.
+    Missing,
+    /// `diff` is only written when the sizes match.
+    Failed {
+        differing: usize,
+        diff: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub time: f32,
+    pub reference: PathBuf,
+    pub outcome: Outcome,
+}
+
+impl Check {
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        matches!(self.outcome, Outcome::Passed { .. } | Outcome::Blessed)
+    }
+}
+
+/// Result of comparing two images of the same size.
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub differing: usize,
+    pub total: usize,
+    /// The expected image darkened, with differing pixels in red.
+    pub diff: RgbaImage,
+}
+
+impl Comparison {
+    #[must_use]
+    #[expect(clippy::cast_precision_loss, reason = "only used as a ratio")]
+    pub fn fraction(&self) -> f32 {
+        self.differing as f32 / self.total.max(1) as f32
+    }
+}
+
+/// Renders the shader at every time in `options.times` on the software
+/// adapter and compares, or blesses, the references.
+///
+/// # Errors
+/// When there is no software adapter, or an image can't be read or written.
+pub fn run(options: &GoldenOptions) -> Result<Vec<Check>, Box<dyn Error>> {
+    let headless = pollster::block_on(Headless::new(true))?;
+    run_on(&headless, options)
+}
+
+/// [`run`] on an existing device.
+///
+/// # Errors
+/// When the shader or an image can't be read or written.
+pub fn run_on(headless: &Headless, options: &GoldenOptions) -> Result<Vec<Check>, Box<dyn Error>> {
+    let shader = shader::Source::read(options.shader.as_deref())?;
+    let mut renderer = Renderer::new(&headless.device, &shader, tonemap::FORMAT, &Geometry::Quad)?;
+    // the default operator, so references don't depend on a curve
+    let mut tonemap = Tonemap::new(&headless.device, TARGET_FORMAT, [1, 1]);
+    std::fs::create_dir_all(&options.references)?;
+
+    let stem = options
+        .shader
+        .as_deref()
+        .and_then(Path::file_stem)
+        .map_or_else(|| "shader".into(), |stem| stem.to_string_lossy());
+
+    let mut checks = Vec::with_capacity(options.times.len());
+    for &time in &options.times {
+        let actual = render_tiled(
+            headless,
+            &mut renderer,
+            &mut tonemap,
+            [options.width, options.height],
+            time,
+            1.0,
+            TILE_SIZE,
+        )?;
+        let name = format!("{stem}_t{time:.3}");
+        let reference = options.references.join(format!("{name}.png"));
+
+        let outcome = if options.bless {
+            actual.save(&reference)?;
+            info!("blessed {}", reference.display());
+            Outcome::Blessed
+        } else if reference.exists() {
+            let expected = image::open(&reference)?.to_rgba8();
+            check(options, &name, &actual, &expected)?
+        } else {
+            warn!("no reference at {}, run with --bless", reference.display());
+            Outcome::Missing
+        };
+
+        checks.push(Check {
+            time,
+            reference,
+            outcome,
+        });
+    }
+
+    Ok(checks)
+}
+
+fn check(
+    options: &GoldenOptions,
+    name: &str,
+    actual: &RgbaImage,
+    expected: &RgbaImage,
+) -> Result<Outcome, Box<dyn Error>> {
+    let diff = options.references.join(format!("{name}.diff.png"));
+
+    if actual.dimensions() != expected.dimensions() {
+        warn!(
+            "{name}: rendered {:?} but the reference is {:?}",
+            actual.dimensions(),
+            expected.dimensions()
+        );
+        actual.save(options.references.join(format!("{name}.actual.png")))?;
+        return Ok(Outcome::Failed {
+            differing: actual.pixels().len(),
+            diff: None,
+        });
+    }
+
+    let comparison = compare(actual, expected, options.tolerance);
+    if comparison.fraction() <= options.max_differing {
+        return Ok(Outcome::Passed {
+            differing: comparison.differing,
+        });
+    }
+
+    comparison.diff.save(&diff)?;
+    actual.save(options.references.join(format!("{name}.actual.png")))?;
+    warn!(
+        "{name}: {} of {} pixels differ, diff written to {}",
+        comparison.differing,
+        comparison.total,
+        diff.display()
+    );
+    Ok(Outcome::Failed {
+        differing: comparison.differing,
+        diff: Some(diff),
+    })
+}
+
+/// Counts the pixels where any channel differs by more than `tolerance`.
+///
+/// # Panics
+/// If the images don't have the same size.
+#[must_use]
+pub fn compare(actual: &RgbaImage, expected: &RgbaImage, tolerance: u8) -> Comparison {
+    assert_eq!(actual.dimensions(), expected.dimensions());
+
+    let mut differing = 0;
+    let mut diff = RgbaImage::new(expected.width(), expected.height());
+    for ((left, right), out) in actual
+        .pixels()
+        .zip(expected.pixels())
+        .zip(diff.pixels_mut())
+    {
+        let distance = left
+            .0
+            .iter()
+            .zip(right.0)
+            .map(|(a, b)| a.abs_diff(b))
+            .max()
+            .unwrap_or(0);
+
+        *out = if distance > tolerance {
+            differing += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let [r, g, b, _] = right.0;
+            Rgba([r / 4, g / 4, b / 4, 255])
+        };
+    }
+
+    Comparison {
+        differing,
+        total: diff.pixels().len(),
+        diff,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::SetupError;
+
+    fn solid(value: u8) -> RgbaImage {
+        RgbaImage::from_pixel(4, 4, Rgba([value, value, value, 255]))
+    }
+
+    #[test]
+    fn identical_images_match() {
+        let comparison = compare(&solid(100), &solid(100), 0);
+        assert_eq!(comparison.differing, 0);
+        assert_eq!(comparison.total, 16);
+    }
+
+    #[test]
+    fn differences_within_tolerance_are_ignored() {
+        assert_eq!(compare(&solid(100), &solid(102), 2).differing, 0);
+        assert_eq!(compare(&solid(100), &solid(103), 2).differing, 16);
+    }
+
+    #[test]
+    fn differing_pixels_are_red_in_the_diff() {
+        let mut actual = solid(100);
+        actual.put_pixel(1, 2, Rgba([0, 100, 100, 255]));
+
+        let comparison = compare(&actual, &solid(100), 0);
+        assert_eq!(comparison.differing, 1);
+        assert!((comparison.fraction() - 1.0 / 16.0).abs() < f32::EPSILON);
+        assert_eq!(*comparison.diff.get_pixel(1, 2), Rgba([255, 0, 0, 255]));
+        assert_eq!(*comparison.diff.get_pixel(0, 0), Rgba([25, 25, 25, 255]));
+    }
+
+    /// Renders the bundled shader against `tests/golden` on the software
+    /// adapter, and is skipped where there is none. Bless new references with
+    /// `GOLDEN_BLESS=1 cargo test`.
+    #[test]
+    fn bundled_shader_matches_references() {
+        let options = GoldenOptions {
+            references: Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden"),
+            bless: std::env::var_os("GOLDEN_BLESS").is_some(),
+            ..GoldenOptions::default()
+        };
+
+        let headless = match pollster::block_on(Headless::new(true)) {
+            Ok(headless) => headless,
+            Err(SetupError::NoAdapter(error)) => {
+                eprintln!("skipping golden images, there is no software adapter: {error}");
+                return;
+            }
+            Err(error) => panic!("golden images need a device: {error}"),
+        };
+        let checks = run_on(&headless, &options).expect("golden run failed");
+        let failed: Vec<_> = checks.iter().filter(|check| !check.passed()).collect();
+        assert!(failed.is_empty(), "golden images differ: {failed:#?}");
+    }
+}
@@ -12,220 +12,232 @@
     clippy::blanket_clippy_restriction_lints,
     reason = "i want to do it as it says"
 )]
+#![allow(
+    clippy::implicit_return,
+    reason = "contradicts clippy::needless_return"
+)]
 // #![warn(clippy::pedantic)]
 
 extern crate alloc;
 use crate::{
+    camera::Camera,
+    clock::Clock,
+    color::{LinearRgb, Srgb},
+    compare::Compare,
+    compute::{self, Compute},
     config::{Configuration as _, DefaultConf as Conf},
+    device_watch::DeviceWatch,
+    error::SetupError,
+    hud::{Hud, Status},
+    hybrid,
+    layout::host_struct,
     model,
+    model::{Geometry, Topology},
+    profiler::Profiler,
+    reflect::{self, Resource},
+    shader,
+    storage::{self, Storage},
+    tonemap::{self, Tonemap},
+    vertex,
 };
+use alloc::collections::BTreeMap;
 use alloc::sync::Arc;
-use core::error::Error;
-use core::mem;
-use std::time::{self, Instant};
-use tracing::instrument;
+use cgmath::{Matrix4, SquareMatrix as _};
+use tracing::{debug, error, instrument, warn};
 use wgpu::util::DeviceExt as _;
 use wgpu::{
-    Adapter, Backends, BindGroup, BlendState, Buffer, BufferUsages, Color, ColorTargetState,
-    ColorWrites, CommandEncoderDescriptor, CompositeAlphaMode, Device, FragmentState, IndexFormat,
-    Instance, InstanceDescriptor, LoadOp, Operations, PipelineCompilationOptions, PipelineLayout,
-    PipelineLayoutDescriptor, PresentMode, Queue, RenderPassColorAttachment, RenderPassDescriptor,
-    RenderPipeline, RenderPipelineDescriptor, ShaderModule, StoreOp, Surface, SurfaceConfiguration,
-    TextureFormat, TextureUsages, TextureViewDescriptor, VertexState, include_wgsl,
+    Adapter, BindGroup, BindGroupLayout, BlendState, Buffer, BufferUsages, Color, ColorTargetState,
+    ColorWrites, CommandEncoder, CommandEncoderDescriptor, CompareFunction, CompositeAlphaMode,
+    DepthBiasState, DepthStencilState, Device, Extent3d, FragmentState, IndexFormat, Instance,
+    LoadOp, Operations, PipelineCompilationOptions, PipelineLayout, PipelineLayoutDescriptor,
+    PresentMode, PrimitiveState, PrimitiveTopology, Queue, RenderPassColorAttachment,
+    RenderPassDepthStencilAttachment, RenderPassDescriptor, RenderPassTimestampWrites,
+    RenderPipeline, RenderPipelineDescriptor, ShaderModule, ShaderStages, StencilState, StoreOp,
+    Surface, SurfaceConfiguration, SurfaceError, TextureDescriptor, TextureDimension,
+    TextureFormat, TextureUsages, TextureView, TextureViewDescriptor, VertexState,
 };
 use winit::{dpi::PhysicalSize, window::Window};
 
+/// Frames a shader has to present before it becomes the one recovery falls
+/// back to, about five seconds at 60 fps.
+const KNOWN_GOOD_FRAMES: u32 = 300;
+
+/// Matrix uniforms until something sets them.
+const IDENTITY: [[f32; 4]; 4] = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+/// Format of the depth buffer models are drawn with.
+pub const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
+
+/// The adapter and device shared by every window.
 #[derive(Debug)]
 #[non_exhaustive]
-pub struct State {
+pub struct Gpu {
     pub adapter: Adapter,
-    pub config: SurfaceConfiguration,
     pub device: Device,
-    pub index_buffer: wgpu::Buffer,
-    pub instance: Instance,
-    pub num_indices: u32,
-    pub num_vertices: u32,
-    pub pipeline_layout: PipelineLayout,
+    pub device_watch: DeviceWatch,
     pub queue: Queue,
-    pub render_pipeline: RenderPipeline,
-    pub shader: ShaderModule,
-    pub size: PhysicalSize<u32>,
-    pub start_time: time::Instant,
-    pub surface: Surface<'static>,
-    pub surface_format: TextureFormat,
-    pub uniforms: Uniforms,
-    pub uniforms_bind_group: BindGroup,
-    pub uniforms_buffer: Buffer,
-    pub vertex_buffer: wgpu::Buffer,
-    pub window: Arc<Window>,
 }
 
-impl State {
-    #[instrument]
-    pub async fn new(window: Arc<Window>) -> Result<State, Box<dyn Error>> {
-        let instance = Instance::new(&InstanceDescriptor {
-            backends: Backends::PRIMARY,
-            ..Default::default()
-        });
-
-        let size = &window.inner_size();
-        #[expect(clippy::expect_used, reason = "unrecoverable")]
-        let surface = instance
-            .create_surface(Arc::clone(&window))
-            .expect("Can't use the current window to render");
+impl Gpu {
+    /// Whether the device stopped working and everything on it has to be
+    /// rebuilt on a new [`Gpu`] before drawing again.
+    #[inline]
+    #[must_use = "returns whether the device was lost"]
+    pub fn is_lost(&self) -> bool {
+        self.device_watch.is_lost()
+    }
 
-        #[expect(clippy::expect_used, reason = "unrecoverable")]
+    /// Picks an adapter that can present to `surface` and opens a device on it.
+    ///
+    /// # Errors
+    /// When there is no suitable adapter or it refuses to give a device.
+    #[inline]
+    pub async fn new(instance: &Instance, surface: &Surface<'static>) -> Result<Gpu, SetupError> {
         let adapter = instance
-            .request_adapter(&Conf::adapter(&surface))
+            .request_adapter(&Conf::adapter(surface))
             .await
-            .expect("can't request an adapter, shutting down");
+            .map_err(SetupError::NoAdapter)?;
 
-        #[expect(clippy::expect_used, reason = "unrecoverable")]
         let (device, queue) = adapter
-            .request_device(&Conf::device_dec(adapter.limits()))
+            .request_device(&Conf::device_dec(
+                adapter.limits(),
+                adapter.features() & Conf::optional_features(),
+            ))
             .await
-            .expect("can't get a device representatio, shutting down");
-
-        let config = Conf::surface_config(&adapter, &surface, size);
-        surface.configure(&device, &config);
-
-        let shader = device.create_shader_module(include_wgsl!("shader.wgsl"));
-
-        #[expect(clippy::as_conversions, reason = "there is no usize bigger than u64")]
-        let buffer = wgpu::VertexBufferLayout {
-            array_stride: mem::size_of::<model::Vertex>() as u64,
-            step_mode: wgpu::VertexStepMode::Vertex,
-            attributes: &[
-                // positions
-                wgpu::VertexAttribute {
-                    offset: 0,
-                    shader_location: 0,
-                    format: wgpu::VertexFormat::Float32x3,
-                },
-                // colors
-                wgpu::VertexAttribute {
-                    offset: mem::size_of::<[f32; 3]>() as u64,
-                    shader_location: 1,
-                    format: wgpu::VertexFormat::Float32x3,
-                },
-            ],
-        };
+            .map_err(SetupError::NoDevice)?;
 
-        let uniforms = Uniforms {
-            screen_size: [0.0, 0.0],
-            time: 0.0,
-            _padding: 0.0,
-        };
-
-        let uniforms_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("uniforms_buffer"),
-            contents: bytemuck::cast_slice(&[uniforms]),
-            usage: wgpu::BufferUsages::UNIFORM | BufferUsages::COPY_DST,
-        });
-
-        let uniforms_bindgroup_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("uniforms_bindgroup_layout"),
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                }],
-            });
-
-        let uniforms_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("uniforms_bind_group"),
-            layout: &uniforms_bindgroup_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: uniforms_buffer.as_entire_binding(),
-            }],
-        });
-
-        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
-            label: Some("pipeline_layout"),
-            bind_group_layouts: &[&uniforms_bindgroup_layout],
-            push_constant_ranges: &[],
-        });
-
-        let render_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
-            layout: Some(&pipeline_layout),
-            vertex: VertexState {
-                module: &shader,
-                entry_point: Some("vs_main"),
-                buffers: &[buffer],
-                compilation_options: PipelineCompilationOptions::default(),
-            },
-            fragment: Some(FragmentState {
-                module: &shader,
-                entry_point: Some("fs_main"),
-                targets: &[Some(ColorTargetState {
-                    format: config.format,
-                    blend: Some(BlendState::REPLACE),
-                    write_mask: ColorWrites::ALL,
-                })],
-                compilation_options: PipelineCompilationOptions::default(),
-            }),
-            primitive: Conf::primitives(),
-            depth_stencil: None,
-            multisample: Conf::multisample(),
-            multiview: None,
-            cache: None,
-            label: Some("render_pipeline"),
-        });
-
-        let vertices = model::vertices();
-        #[expect(clippy::expect_used, reason = "Unrecoverable error")]
-        let num_vertices =
-            u32::try_from(vertices.len()).expect("Couldn't convert from vertices.len() to u32");
+        Ok(Gpu {
+            device_watch: DeviceWatch::new(&device),
+            adapter,
+            device,
+            queue,
+        })
+    }
+}
 
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("vertex_buffer"),
-            contents: bytemuck::cast_slice(&vertices),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
+/// A window previewing one shader.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct State {
+    /// Where models are seen from.
+    pub camera: Camera,
+    pub clock: Clock,
+    /// Second shader shown next to `shader`, see [`State::compare_with`].
+    pub compare: Option<Compare>,
+    pub config: SurfaceConfiguration,
+    /// Frames presented since `shader` was put on the current device.
+    pub frames_on_shader: u32,
+    /// What `shader` is drawn on, kept to rebuild the renderer.
+    pub geometry: Geometry,
+    pub hud: Hud,
+    /// Shader to fall back to when the device is lost before `shader` has
+    /// run for [`KNOWN_GOOD_FRAMES`], at first the default one for
+    /// `geometry`.
+    pub known_good: shader::Source,
+    /// Places `geometry` in the world, see [`model::Mesh::fit`].
+    pub model: Matrix4<f32>,
+    pub profiler: Profiler,
+    pub renderer: Renderer,
+    /// Physical pixels per logical pixel of the window's monitor.
+    pub scale_factor: f32,
+    pub shader: shader::Source,
+    pub size: PhysicalSize<u32>,
+    pub surface: Surface<'static>,
+    pub surface_format: TextureFormat,
+    /// Where `renderer` draws, and how that reaches the window.
+    pub tonemap: Tonemap,
+    pub uniforms: Uniforms,
+    pub window: Arc<Window>,
+}
 
-        let indices = model::INDICES;
-        #[expect(clippy::expect_used, reason = "Unrecoverable error")]
-        let num_indices =
-            u32::try_from(indices.len()).expect("Couldn't convert from indices.len() to u32");
+impl State {
+    /// # Errors
+    /// When the adapter of `gpu` can't present to `surface`, or `vs_main`
+    /// reads vertex inputs `geometry` doesn't have.
+    #[instrument(skip(gpu, surface))]
+    #[inline]
+    pub fn new(
+        gpu: &Gpu,
+        window: Arc<Window>,
+        surface: Surface<'static>,
+        shader: shader::Source,
+        geometry: Geometry,
+    ) -> Result<State, SetupError> {
+        let adapter = &gpu.adapter;
+        let device = &gpu.device;
 
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("index_buffer"),
-            contents: bytemuck::cast_slice(indices),
-            usage: wgpu::BufferUsages::INDEX,
-        });
+        let size = &window.inner_size();
+        if !adapter.is_surface_supported(&surface) {
+            return Err(SetupError::SurfaceUnsupported);
+        }
+        let config =
+            Conf::surface_config(adapter, &surface, size).ok_or(SetupError::SurfaceUnsupported)?;
+        if size.width > 0 && size.height > 0 {
+            surface.configure(device, &config);
+        }
+
+        let mut renderer = Renderer::new(device, &shader, tonemap::FORMAT, &geometry)?;
+        renderer.resize(device, [size.width, size.height]);
+        let tonemap = Tonemap::new(
+            device,
+            config.format.add_srgb_suffix(),
+            [size.width, size.height],
+        );
+        let profiler = Profiler::new(device, &gpu.queue);
+        let hud = Hud::new(device, config.format.add_srgb_suffix());
 
         Ok(State {
-            start_time: Instant::now(),
-            uniforms,
-            surface_format: config.format,
-            instance,
-            adapter,
+            camera: Camera::default(),
+            clock: Clock::new(),
+            model: geometry
+                .mesh()
+                .map_or_else(Matrix4::identity, model::Mesh::fit),
+            compare: None,
+            hud,
             shader,
-            pipeline_layout,
-            uniforms_buffer,
-            uniforms_bind_group,
-            render_pipeline,
+            known_good: shader::Source::default_for(&geometry),
+            frames_on_shader: 0,
+            geometry,
+            uniforms: Uniforms::new([0.0, 0.0], 0.0),
+            surface_format: config.format,
+            renderer,
+            profiler,
+            scale_factor: scale_factor(window.scale_factor()),
             size: *size,
-            device,
-            queue,
             surface,
+            tonemap,
             window,
             config,
-            vertex_buffer,
-            num_vertices,
-            index_buffer,
-            num_indices,
         })
     }
 
-    pub fn configure_surface(&self) {
+    /// Splits the window between its own shader, on the left, and `shader`.
+    ///
+    /// # Errors
+    /// When `shader` can't draw the window's geometry, see [`Renderer::new`].
+    #[inline]
+    pub fn compare_with(
+        &mut self,
+        device: &Device,
+        shader: shader::Source,
+    ) -> Result<(), SetupError> {
+        self.compare = Some(Compare::new(
+            device,
+            shader,
+            tonemap::FORMAT,
+            &self.geometry,
+            [self.size.width, self.size.height],
+        )?);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn configure_surface(&self, device: &Device) {
         let surface_config = SurfaceConfiguration {
             usage: TextureUsages::RENDER_ATTACHMENT,
             format: self.surface_format,
@@ -237,18 +249,112 @@ impl State {
             desired_maximum_frame_latency: 2,
             present_mode: PresentMode::AutoVsync,
         };
-        self.surface.configure(&self.device, &surface_config);
+        self.surface.configure(device, &surface_config);
     }
 
-    /// # Panics
-    pub fn render(&mut self) {
+    /// Whether the window has no area to draw to, as happens while minimized.
+    #[inline]
+    #[must_use = "returns whether there is nothing to draw"]
+    pub const fn is_minimized(&self) -> bool {
+        self.size.width == 0 || self.size.height == 0
+    }
+
+    /// Rebuilds everything that lives on the device on the new `gpu`,
+    /// keeping the playback time and HUD. When `suspect`, this window's
+    /// shader was running as the device was lost, and if it hadn't proved
+    /// itself yet it is swapped for the last known-good one, so a shader that
+    /// hangs the GPU doesn't do it again straight away. The fallback is only
+    /// taken when it can draw the window's geometry.
+    ///
+    /// # Errors
+    /// When a shader can't draw the geometry anymore, see [`Renderer::new`].
+    #[inline]
+    pub fn rebuild(&mut self, gpu: &Gpu, suspect: bool) -> Result<(), SetupError> {
+        let fallback = (suspect && self.frames_on_shader < KNOWN_GOOD_FRAMES).then(|| {
+            Renderer::new(
+                &gpu.device,
+                &self.known_good,
+                tonemap::FORMAT,
+                &self.geometry,
+            )
+        });
+        self.renderer = match fallback {
+            Some(Ok(renderer)) => {
+                warn!(
+                    "{} was lost after {} frames, falling back to {}",
+                    self.shader.name, self.frames_on_shader, self.known_good.name
+                );
+                self.shader = self.known_good.clone();
+                renderer
+            }
+            Some(Err(error)) => {
+                warn!(
+                    "{} was lost after {} frames, keeping it as {} can't draw here: {error}",
+                    self.shader.name, self.frames_on_shader, self.known_good.name
+                );
+                Renderer::new(&gpu.device, &self.shader, tonemap::FORMAT, &self.geometry)?
+            }
+            None => Renderer::new(&gpu.device, &self.shader, tonemap::FORMAT, &self.geometry)?,
+        };
+        self.frames_on_shader = 0;
+
+        let hud_visible = self.hud.visible;
+        self.renderer
+            .resize(&gpu.device, [self.size.width, self.size.height]);
+        let (operator, exposure) = (self.tonemap.operator, self.tonemap.exposure);
+        self.tonemap = Tonemap::new(
+            &gpu.device,
+            self.surface_format.add_srgb_suffix(),
+            [self.size.width, self.size.height],
+        );
+        self.tonemap.operator = operator;
+        self.tonemap.exposure = exposure;
+        self.profiler = Profiler::new(&gpu.device, &gpu.queue);
+        self.hud = Hud::new(&gpu.device, self.surface_format.add_srgb_suffix());
+        self.hud.visible = hud_visible;
+
+        if let Some(old) = self.compare.take() {
+            self.compare_with(&gpu.device, old.b_shader)?;
+            if let Some(compare) = self.compare.as_mut() {
+                compare.split = old.split;
+                compare.view = old.view;
+            }
+        }
+
+        if !self.is_minimized() {
+            self.configure_surface(&gpu.device);
+        }
+        Ok(())
+    }
+
+    /// Draws and presents one frame, or nothing while minimized. When the
+    /// surface can't hand out a texture the frame is skipped, reconfiguring
+    /// the surface first if it was lost or went out of date.
+    ///
+    /// # Errors
+    /// [`SurfaceError::OutOfMemory`], after which there is no point in
+    /// drawing more frames.
+    #[inline]
+    pub fn render(&mut self, gpu: &Gpu) -> Result<(), SurfaceError> {
+        if self.is_minimized() {
+            return Ok(());
+        }
+        self.profiler.begin_frame(&gpu.device);
+
         let frame = match self.surface.get_current_texture() {
-            Ok(f) => f,
-            Err(e) => {
-                #[cfg(debug_assertions)]
-                panic!("Hay dio quejesto:\n{e:#?}");
-                #[expect(unused, reason = "")]
-                return ();
+            Ok(frame) => frame,
+            Err(error @ (SurfaceError::Lost | SurfaceError::Outdated)) => {
+                warn!("{error}, reconfiguring the surface");
+                self.configure_surface(&gpu.device);
+                return Ok(());
+            }
+            Err(error @ (SurfaceError::Timeout | SurfaceError::Other)) => {
+                warn!("{error}, skipping the frame");
+                return Ok(());
+            }
+            Err(error @ SurfaceError::OutOfMemory) => {
+                error!("{error}");
+                return Err(error);
             }
         };
 
@@ -257,69 +363,615 @@ impl State {
             ..Default::default()
         });
 
-        let mut encoder = self
+        let mut encoder = gpu
             .device
             .create_command_encoder(&CommandEncoderDescriptor::default());
 
+        #[expect(
+            clippy::as_conversions,
+            clippy::cast_precision_loss,
+            reason = "window sizes fit in f32"
+        )]
+        let screen_size = [self.size.width as f32, self.size.height as f32];
+        self.uniforms = Uniforms::new(screen_size, self.clock.seconds())
+            .with_scale_factor(self.scale_factor)
+            .with_camera(&self.camera)
+            .with_model(self.model)
+            .with_vertex_count(self.geometry.vertex_count());
+
+        if let Some(compare) = self.compare.as_ref() {
+            self.renderer.draw(
+                &gpu.queue,
+                &mut encoder,
+                compare.a(),
+                &self.uniforms,
+                self.profiler.timestamp_writes("main"),
+            );
+            compare.draw(
+                &gpu.queue,
+                &mut encoder,
+                self.tonemap.target(),
+                &self.uniforms,
+                self.profiler.timestamp_writes("compare"),
+            );
+        } else {
+            self.renderer.draw(
+                &gpu.queue,
+                &mut encoder,
+                self.tonemap.target(),
+                &self.uniforms,
+                self.profiler.timestamp_writes("main"),
+            );
+        }
+        self.tonemap.draw(
+            &gpu.queue,
+            &mut encoder,
+            &view,
+            self.profiler.timestamp_writes("tonemap"),
+        );
+
+        if self.hud.visible {
+            let status = self.status();
+            self.hud.draw(
+                &gpu.queue,
+                &mut encoder,
+                &view,
+                &status,
+                self.profiler.timestamp_writes("hud"),
+            );
+        }
+        self.profiler.resolve(&mut encoder);
+
+        gpu.queue.submit([encoder.finish()]);
+        self.profiler.submitted();
+        self.window.pre_present_notify();
+        let suboptimal = frame.suboptimal;
+        frame.present();
+
+        if suboptimal {
+            debug!("suboptimal surface, reconfiguring");
+            self.configure_surface(&gpu.device);
+        }
+
+        self.frames_on_shader = self.frames_on_shader.saturating_add(1);
+        if self.frames_on_shader == KNOWN_GOOD_FRAMES {
+            self.known_good = self.shader.clone();
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn resize(&mut self, device: &Device, new_size: PhysicalSize<u32>) {
+        self.size = new_size;
+
+        // a surface can't be configured with no area, wait for a real size
+        if !self.is_minimized() {
+            self.configure_surface(device);
+            self.renderer
+                .resize(device, [new_size.width, new_size.height]);
+            self.tonemap
+                .resize(device, [new_size.width, new_size.height]);
+            if let Some(compare) = self.compare.as_mut() {
+                compare.resize(device, tonemap::FORMAT, [new_size.width, new_size.height]);
+            }
+        }
+    }
+
+    /// Follows the window to a monitor with a different pixel density. The
+    /// new size arrives separately through [`State::resize`].
+    #[inline]
+    pub fn set_scale_factor(&mut self, new_scale_factor: f64) {
+        self.scale_factor = scale_factor(new_scale_factor);
+    }
+
+    /// Snapshot of the numbers shown by the HUD.
+    #[inline]
+    #[must_use = "returns the status"]
+    pub fn status(&self) -> Status {
+        Status {
+            fps: self.profiler.fps(),
+            cpu_ms: self.profiler.cpu_work.mean(),
+            gpu_ms: self.profiler.gpu_frame(),
+            resolution: [self.size.width, self.size.height],
+            time: self.clock.seconds(),
+            paused: self.clock.is_paused(),
+            shader: match self.compare.as_ref() {
+                Some(compare) => format!(
+                    "{} VS {}",
+                    self.shader.file_name(),
+                    compare.b_shader.file_name()
+                ),
+                None => self.shader.file_name(),
+            },
+            tonemap: self.tonemap.operator,
+            exposure: self.tonemap.exposure,
+        }
+    }
+}
+
+/// Everything needed to draw the shader into a color target, independent of
+/// where that target comes from (a window surface or an offscreen texture).
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct Renderer {
+    /// Run before the render pass when the shader has a compute entry point.
+    pub compute: Option<Compute>,
+    /// Depth buffer for models, sized by [`Renderer::resize`].
+    pub depth: Option<TextureView>,
+    pub index_buffer: Buffer,
+    pub num_indices: u32,
+    pub num_vertices: u32,
+    pub pipeline_layout: PipelineLayout,
+    /// Ray marches the rest of the scene around a model, see [`hybrid`].
+    pub raymarch: Option<RenderPipeline>,
+    pub render_pipeline: RenderPipeline,
+    pub shader: ShaderModule,
+    /// The shader's storage buffers, kept from one frame to the next.
+    pub storage: Option<Storage>,
+    pub uniforms_bind_group: BindGroup,
+    pub uniforms_buffer: Buffer,
+    pub vertex_buffer: Buffer,
+}
+
+impl Renderer {
+    /// Uploads `uniforms` and records one render pass drawing the shader into
+    /// `view`, timed through `timestamp_writes` when given. The compute pass,
+    /// if any, goes first, and steps the storage buffers once per call.
+    #[inline]
+    pub fn draw(
+        &self,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        view: &TextureView,
+        uniforms: &Uniforms,
+        timestamp_writes: Option<RenderPassTimestampWrites<'_>>,
+    ) {
+        queue.write_buffer(&self.uniforms_buffer, 0, bytemuck::cast_slice(&[*uniforms]));
+        if let Some(compute) = self.compute.as_ref() {
+            compute.dispatch(
+                encoder,
+                &self.uniforms_bind_group,
+                self.storage
+                    .as_ref()
+                    .map(|buffers| &buffers.compute_bind_group),
+            );
+        }
+
+        // sky blue where nothing is drawn, in linear light like shader output
+        let LinearRgb(clear) = Srgb::hex(0x6d_d0_fa).to_linear();
         let mut renderpass = encoder.begin_render_pass(&RenderPassDescriptor {
             label: None,
             color_attachments: &[Some(RenderPassColorAttachment {
-                view: &view,
+                view,
                 resolve_target: None,
                 ops: Operations {
                     load: LoadOp::Clear(Color {
-                        r: f64::from(109_i32 / 255_i32),
-                        g: f64::from(208_i32 / 255_i32),
-                        b: f64::from(250_i32 / 255_i32),
+                        r: f64::from(clear[0]),
+                        g: f64::from(clear[1]),
+                        b: f64::from(clear[2]),
                         a: 0.0,
                     }),
                     store: StoreOp::Store,
                 },
             })],
+            depth_stencil_attachment: self.depth.as_ref().map(|depth| {
+                RenderPassDepthStencilAttachment {
+                    view: depth,
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Clear(1.0),
+                        store: StoreOp::Discard,
+                    }),
+                    stencil_ops: None,
+                }
+            }),
+            timestamp_writes,
             ..Default::default()
         });
 
-        let screen_size = [self.size.width as f32, self.size.height as f32];
+        renderpass.set_pipeline(&self.render_pipeline); // 2.
+        renderpass.set_bind_group(0, &self.uniforms_bind_group, &[]);
+        match (self.compute.as_ref(), self.storage.as_ref()) {
+            (Some(compute), _) => renderpass.set_bind_group(1, &compute.output_bind_group, &[]),
+            (None, Some(buffers)) => renderpass.set_bind_group(1, &buffers.empty_bind_group, &[]),
+            (None, None) => {}
+        }
+        if let Some(buffers) = self.storage.as_ref() {
+            renderpass.set_bind_group(storage::GROUP, &buffers.render_bind_group, &[]);
+        }
+        if self.num_indices == 0 {
+            renderpass.draw(0..self.num_vertices, 0..1);
+        } else {
+            renderpass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            renderpass.set_index_buffer(self.index_buffer.slice(..), IndexFormat::Uint32);
+            renderpass.draw_indexed(0..self.num_indices, 0, 0..1);
+        }
+
+        if let Some(raymarch) = self.raymarch.as_ref() {
+            renderpass.set_pipeline(raymarch);
+            renderpass.draw(0..3, 0..1);
+        }
+    }
 
-        self.uniforms = Uniforms {
-            screen_size: [screen_size[0], screen_size[1]],
-            time: self.start_time.elapsed().as_secs_f32(),
-            _padding: 0.0,
+    /// Draws the shader on `geometry`. A shader with a
+    /// [`compute::ENTRY_POINT`] and no `fs_main` gets the compute output
+    /// drawn as is. Call [`Renderer::resize`] before drawing.
+    ///
+    /// # Errors
+    /// [`SetupError::VertexInput`] when the `vs_main` drawing `geometry`
+    /// reads inputs it doesn't have. That is the shader's own, unless the
+    /// compute output or a model in a ray marched scene is drawn instead.
+    ///
+    /// # Panics
+    /// If the model has more vertices or indices than fit in a `u32`.
+    #[inline]
+    #[must_use = "returns a new renderer"]
+    pub fn new(
+        device: &Device,
+        source: &shader::Source,
+        format: TextureFormat,
+        geometry: &Geometry,
+    ) -> Result<Renderer, SetupError> {
+        // a model in a ray marched scene is drawn with the lit shader
+        let hybrid = geometry.mesh().is_some() && source.entry_point(hybrid::ENTRY_POINT).is_some();
+        let computes = source.entry_point(compute::ENTRY_POINT);
+        let drawn = match (computes, source.entry_point("fs_main"), hybrid) {
+            (Some(_), None, _) => Some(shader::Source::compute_blit()),
+            (_, _, true) => Some(shader::Source::lit()),
+            _ => None,
         };
-
-        self.queue.write_buffer(
-            &self.uniforms_buffer,
-            0,
-            bytemuck::cast_slice(&[self.uniforms]),
+        let vertex_stage = drawn.as_ref().unwrap_or(source);
+        vertex::check(
+            &vertex_stage.name,
+            &vertex_stage.module,
+            geometry.vertex_layout().as_ref(),
+        )?;
+        // every pipeline shares the layout, which has to cover what each uses
+        let bindings: Vec<_> = source
+            .bindings
+            .iter()
+            .chain(drawn.iter().flat_map(|other| &other.bindings))
+            .copied()
+            .collect();
+
+        let (uniforms_buffer, uniforms_bindgroup_layout, uniforms_bind_group) =
+            uniforms(device, &reflect::visibility(&bindings, Resource::Uniforms));
+
+        let module = source.compile(device);
+        let storage = Storage::new(
+            device,
+            &source.buffers,
+            &reflect::visibility(&bindings, Resource::StorageBuffer),
         );
+        let compute = computes.map(|entry_point| {
+            Compute::new(
+                device,
+                &module,
+                &uniforms_bindgroup_layout,
+                storage.as_ref().map(|buffers| &buffers.compute_layout),
+                reflect::visibility(&bindings, Resource::ComputeOutput)
+                    .into_values()
+                    .fold(ShaderStages::NONE, |stages, visibility| stages | visibility)
+                    .difference(ShaderStages::COMPUTE),
+                entry_point.workgroup_size,
+            )
+        });
+        let shader = drawn
+            .as_ref()
+            .map_or_else(|| module.clone(), |other| other.compile(device));
+
+        let mut bind_group_layouts = vec![&uniforms_bindgroup_layout];
+        match (compute.as_ref(), storage.as_ref()) {
+            (Some(pass), _) => bind_group_layouts.push(&pass.output_layout),
+            (None, Some(buffers)) => bind_group_layouts.push(&buffers.empty_layout),
+            (None, None) => {}
+        }
+        if let Some(buffers) = storage.as_ref() {
+            bind_group_layouts.push(&buffers.render_layout);
+        }
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("pipeline_layout"),
+            bind_group_layouts: &bind_group_layouts,
+            push_constant_ranges: &[],
+        });
 
-        renderpass.set_pipeline(&self.render_pipeline); // 2.
-        renderpass.set_bind_group(0, &self.uniforms_bind_group, &[]);
-        renderpass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-        renderpass.set_index_buffer(self.index_buffer.slice(..), IndexFormat::Uint16);
-        renderpass.draw_indexed(0..self.num_indices, 0, 0..1);
+        let render_pipeline = render_pipeline(device, &shader, &pipeline_layout, format, geometry);
+        let raymarch = hybrid.then(|| hybrid::pipeline(device, &module, &pipeline_layout, format));
 
-        drop(renderpass);
+        let (vertex_buffer, num_vertices, index_buffer, num_indices) =
+            geometry_buffers(device, geometry);
 
-        self.queue.submit([encoder.finish()]);
-        self.window.pre_present_notify();
-        frame.present();
+        Ok(Renderer {
+            compute,
+            depth: geometry.mesh().map(|_| depth_texture(device, [1, 1])),
+            index_buffer,
+            num_indices,
+            num_vertices,
+            pipeline_layout,
+            raymarch,
+            render_pipeline,
+            shader,
+            storage,
+            uniforms_bind_group,
+            uniforms_buffer,
+            vertex_buffer,
+        })
     }
 
-    pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
-        self.size = new_size;
+    /// Sizes the compute output and the depth buffer for a target of `size`
+    /// pixels, which in a tiled export is one tile of the frame.
+    #[inline]
+    pub fn resize(&mut self, device: &Device, size: [u32; 2]) {
+        if let Some(compute) = self.compute.as_mut() {
+            compute.resize(device, size);
+        }
+        if let Some(depth) = self.depth.as_mut() {
+            *depth = depth_texture(device, size);
+        }
+    }
+}
 
-        // reconfigure the surface
-        self.configure_surface();
+host_struct! {
+    #[derive(Debug)]
+    pub struct Uniforms {
+        /// Size in pixels of the whole frame.
+        resolution: [f32; 2],
+        /// Seconds since the start of playback.
+        time: f32,
+        /// Physical pixels per logical pixel, so 2D shaders can keep line widths
+        /// constant across pixel densities. Also keeps `tile_offset` on the 8
+        /// byte alignment WGSL gives a `vec2<f32>`.
+        scale_factor: f32,
+        /// Pixel offset of the rendered region inside the frame.
+        tile_offset: [f32; 2],
+        /// Vertices in the draw, for shaders placing them by index.
+        vertex_count: u32,
+        /// Puts `model` on the 16 byte alignment of a WGSL `mat4x4<f32>`.
+        _padding: f32,
+        /// Object to world space.
+        model: [[f32; 4]; 4],
+        /// World to camera space.
+        view: [[f32; 4]; 4],
+        /// Camera to clip space.
+        projection: [[f32; 4]; 4],
     }
 }
 
-#[repr(C)]
-#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
-pub struct Uniforms {
-    screen_size: [f32; 2],
-    time: f32,
-    _padding: f32,
+impl Uniforms {
+    #[inline]
+    #[must_use = "returns new uniforms"]
+    pub const fn new(screen_size: [f32; 2], time: f32) -> Uniforms {
+        Uniforms {
+            resolution: screen_size,
+            time,
+            scale_factor: 1.0,
+            tile_offset: [0.0, 0.0],
+            vertex_count: 0,
+            _padding: 0.0,
+            model: IDENTITY,
+            view: IDENTITY,
+            projection: IDENTITY,
+        }
+    }
+
+    /// Physical pixels per logical pixel.
+    #[inline]
+    #[must_use = "returns the scale factor"]
+    pub const fn scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+
+    /// Size in pixels of the whole frame.
+    #[inline]
+    #[must_use = "returns the screen size"]
+    pub const fn screen_size(&self) -> [f32; 2] {
+        self.resolution
+    }
+
+    /// The view and projection of `camera`, for the current screen size.
+    #[inline]
+    #[must_use = "returns the updated uniforms"]
+    pub fn with_camera(mut self, camera: &Camera) -> Uniforms {
+        self.view = camera.view().into();
+        self.projection = camera.projection(self.resolution).into();
+        self
+    }
+
+    /// Object to world space transform of the model.
+    #[inline]
+    #[must_use = "returns the updated uniforms"]
+    pub fn with_model(mut self, model: Matrix4<f32>) -> Uniforms {
+        self.model = model.into();
+        self
+    }
+
+    /// Physical pixels per logical pixel, 1 unless set.
+    #[inline]
+    #[must_use = "returns the updated uniforms"]
+    pub const fn with_scale_factor(mut self, scale_factor: f32) -> Uniforms {
+        self.scale_factor = scale_factor;
+        self
+    }
+
+    /// Pixel offset of the rendered region inside a `screen_size` image, for
+    /// drawing one tile of a frame that is larger than the render target.
+    #[inline]
+    #[must_use = "returns the updated uniforms"]
+    pub const fn with_tile_offset(mut self, tile_offset: [f32; 2]) -> Uniforms {
+        self.tile_offset = tile_offset;
+        self
+    }
+
+    /// Vertices in the draw, see [`Geometry::vertex_count`].
+    #[inline]
+    #[must_use = "returns the updated uniforms"]
+    pub const fn with_vertex_count(mut self, vertex_count: u32) -> Uniforms {
+        self.vertex_count = vertex_count;
+        self
+    }
+}
+
+/// A `size` depth buffer, at least one pixel in size.
+fn depth_texture(device: &Device, size: [u32; 2]) -> TextureView {
+    device
+        .create_texture(&TextureDescriptor {
+            label: Some("depth_texture"),
+            size: Extent3d {
+                width: size[0].max(1),
+                height: size[1].max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: DEPTH_FORMAT,
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        })
+        .create_view(&TextureViewDescriptor::default())
+}
+
+/// The uniforms buffer, and its layout and bind group with an entry at each
+/// binding of `visibility`, for the stages it gives.
+#[expect(clippy::single_call_fn, reason = "keeps Renderer::new readable")]
+fn uniforms(
+    device: &Device,
+    visibility: &BTreeMap<u32, ShaderStages>,
+) -> (Buffer, BindGroupLayout, BindGroup) {
+    let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("uniforms_buffer"),
+        contents: bytemuck::cast_slice(&[Uniforms::new([0.0, 0.0], 0.0)]),
+        usage: wgpu::BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+    });
+
+    let layout_entries: Vec<_> = visibility
+        .iter()
+        .map(|(&binding, &stages)| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: stages,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        })
+        .collect();
+    let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("uniforms_bindgroup_layout"),
+        entries: &layout_entries,
+    });
+
+    let entries: Vec<_> = visibility
+        .keys()
+        .map(|&binding| wgpu::BindGroupEntry {
+            binding,
+            resource: buffer.as_entire_binding(),
+        })
+        .collect();
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("uniforms_bind_group"),
+        layout: &layout,
+        entries: &entries,
+    });
+
+    (buffer, layout, bind_group)
+}
+
+/// Vertex and index buffers of `geometry`, with their lengths.
+#[expect(clippy::single_call_fn, reason = "keeps Renderer::new readable")]
+fn geometry_buffers(device: &Device, geometry: &Geometry) -> (Buffer, u32, Buffer, u32) {
+    let quad = model::vertices();
+    // bare vertices get empty buffers, which are never bound
+    let (vertices, indices): (&[u8], &[u32]) = if let Some(mesh) = geometry.mesh() {
+        (bytemuck::cast_slice(&mesh.vertices), &mesh.indices)
+    } else if *geometry == Geometry::Quad {
+        (bytemuck::cast_slice(&quad), model::INDICES)
+    } else {
+        (&[], &[])
+    };
+    let num_vertices = geometry.vertex_count();
+
+    let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("vertex_buffer"),
+        contents: vertices,
+        usage: wgpu::BufferUsages::VERTEX,
+    });
+
+    #[expect(clippy::expect_used, reason = "Unrecoverable error")]
+    let num_indices =
+        u32::try_from(indices.len()).expect("Couldn't convert from indices.len() to u32");
+
+    let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("index_buffer"),
+        contents: bytemuck::cast_slice(indices),
+        usage: wgpu::BufferUsages::INDEX,
+    });
+
+    (vertex_buffer, num_vertices, index_buffer, num_indices)
+}
+
+/// Pipeline drawing `geometry` with the `vs_main` and `fs_main` entry points
+/// of `shader` into a `format` target.
+#[expect(clippy::single_call_fn, reason = "keeps Renderer::new readable")]
+fn render_pipeline(
+    device: &Device,
+    shader: &ShaderModule,
+    layout: &PipelineLayout,
+    format: TextureFormat,
+    geometry: &Geometry,
+) -> RenderPipeline {
+    let vertex_layout = geometry.vertex_layout();
+    let primitive = geometry
+        .topology()
+        .map_or_else(Conf::primitives, |topology| PrimitiveState {
+            topology: match topology {
+                Topology::Points => PrimitiveTopology::PointList,
+                Topology::Lines => PrimitiveTopology::LineList,
+                Topology::Triangles => PrimitiveTopology::TriangleList,
+            },
+            cull_mode: None,
+            ..Conf::primitives()
+        });
+
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        layout: Some(layout),
+        vertex: VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            buffers: vertex_layout.as_slice(),
+            compilation_options: PipelineCompilationOptions::default(),
+        },
+        fragment: Some(FragmentState {
+            module: shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(ColorTargetState {
+                format,
+                blend: Some(BlendState::REPLACE),
+                write_mask: ColorWrites::ALL,
+            })],
+            compilation_options: PipelineCompilationOptions::default(),
+        }),
+        primitive,
+        depth_stencil: geometry.mesh().map(|_| DepthStencilState {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: CompareFunction::Less,
+            stencil: StencilState::default(),
+            bias: DepthBiasState::default(),
+        }),
+        multisample: Conf::multisample(),
+        multiview: None,
+        cache: None,
+        label: Some("render_pipeline"),
+    })
 }
 
-struct hay;
+/// Narrows winit's scale factor to what the shader gets.
+#[expect(
+    clippy::as_conversions,
+    clippy::cast_possible_truncation,
+    reason = "scale factors are small numbers"
+)]
+fn scale_factor(scale_factor: f64) -> f32 {
+    scale_factor as f32
+}
@@ -0,0 +1,372 @@
+use crate::tonemap::Operator;
+use bytemuck::Zeroable as _;
+use wgpu::util::DeviceExt as _;
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingResource, BindingType, BlendState, Buffer, BufferBindingType,
+    BufferUsages, ColorTargetState, ColorWrites, CommandEncoder, Device, Extent3d, FragmentState,
+    LoadOp, MultisampleState, Operations, Origin3d, PipelineCompilationOptions, PipelineLayout,
+    PipelineLayoutDescriptor, PrimitiveState, PrimitiveTopology, Queue, RenderPassColorAttachment,
+    RenderPassDescriptor, RenderPassTimestampWrites, RenderPipeline, RenderPipelineDescriptor,
+    ShaderModule, ShaderStages, StoreOp, TexelCopyBufferLayout, TexelCopyTextureInfo, Texture,
+    TextureAspect, TextureDescriptor, TextureDimension, TextureFormat, TextureSampleType,
+    TextureUsages, TextureView, TextureViewDescriptor, TextureViewDimension, VertexState,
+    include_wgsl,
+};
+
+const GLYPH_WIDTH: u32 = 5;
+const GLYPH_HEIGHT: u32 = 7;
+const CELL_WIDTH: u32 = GLYPH_WIDTH + 1;
+const CELL_HEIGHT: u32 = GLYPH_HEIGHT + 2;
+const PADDING: u32 = 4;
+const COLUMNS: u32 = 32;
+const LINES: u32 = 8;
+const TEXTURE_WIDTH: u32 = COLUMNS * CELL_WIDTH + 2 * PADDING;
+const TEXTURE_HEIGHT: u32 = LINES * CELL_HEIGHT + 2 * PADDING;
+/// Framebuffer pixels per font pixel.
+const SCALE: f32 = 2.0;
+/// Distance from the top left corner of the window, in framebuffer pixels.
+const MARGIN: f32 = 8.0;
+
+const BACKGROUND: [u8; 4] = [0, 0, 0, 160];
+const FOREGROUND: [u8; 4] = [255, 255, 255, 255];
+
+/// What the overlay reports about the current frame.
+#[derive(Debug, Clone)]
+pub struct Status {
+    pub fps: Option<f32>,
+    pub cpu_ms: Option<f32>,
+    pub gpu_ms: Option<f32>,
+    pub resolution: [u32; 2],
+    pub time: f32,
+    pub paused: bool,
+    pub shader: String,
+    pub tonemap: Operator,
+    /// Stops, see [`crate::tonemap::Tonemap::exposure`].
+    pub exposure: f32,
+}
+
+impl Status {
+    fn lines(&self) -> [String; 8] {
+        let millis =
+            |value: Option<f32>| value.map_or_else(|| "N/A".into(), |ms| format!("{ms:.2} MS"));
+
+        [
+            format!(
+                "FPS  {}",
+                self.fps
+                    .map_or_else(|| "N/A".into(), |fps| format!("{fps:.1}"))
+            ),
+            format!("CPU  {}", millis(self.cpu_ms)),
+            format!("GPU  {}", millis(self.gpu_ms)),
+            format!("RES  {}X{}", self.resolution[0], self.resolution[1]),
+            format!("TIME {:.2}", self.time),
+            (if self.paused { "PAUSED" } else { "PLAYING" }).into(),
+            self.shader.clone(),
+            format!("TONE {} {:+.1} EV", self.tonemap, self.exposure),
+        ]
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct HudUniforms {
+    origin: [f32; 2],
+    size: [f32; 2],
+    screen: [f32; 2],
+    scale: f32,
+    _padding: f32,
+}
+
+/// Text overlay drawn from the host on top of the finished frame.
+#[derive(Debug)]
+pub struct Hud {
+    pub visible: bool,
+    bind_group: BindGroup,
+    pipeline: RenderPipeline,
+    pixels: Vec<u8>,
+    texture: Texture,
+    uniforms_buffer: Buffer,
+}
+
+impl Hud {
+    #[must_use]
+    pub fn new(device: &Device, format: TextureFormat) -> Hud {
+        let shader = device.create_shader_module(include_wgsl!("hud.wgsl"));
+
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("hud_text"),
+            size: Extent3d {
+                width: TEXTURE_WIDTH,
+                height: TEXTURE_HEIGHT,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8Unorm,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        let uniforms_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("hud_uniforms_buffer"),
+            contents: bytemuck::bytes_of(&HudUniforms::zeroed()),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("hud_bindgroup_layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::VERTEX_FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: false },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("hud_bind_group"),
+            layout: &bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: uniforms_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(
+                        &texture.create_view(&TextureViewDescriptor::default()),
+                    ),
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("hud_pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = pipeline(device, &shader, &pipeline_layout, format);
+
+        Hud {
+            visible: true,
+            bind_group,
+            pipeline,
+            pixels: vec![0; (TEXTURE_WIDTH * TEXTURE_HEIGHT * 4) as usize],
+            texture,
+            uniforms_buffer,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    /// Rasterizes `status` and records a pass blending it over `view`.
+    #[expect(clippy::cast_precision_loss, reason = "sizes are small")]
+    pub fn draw(
+        &mut self,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        view: &TextureView,
+        status: &Status,
+        timestamp_writes: Option<RenderPassTimestampWrites<'_>>,
+    ) {
+        let lines = status.lines();
+        let columns = self.rasterize(&lines);
+
+        queue.write_texture(
+            TexelCopyTextureInfo {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            &self.pixels,
+            TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(TEXTURE_WIDTH * 4),
+                rows_per_image: Some(TEXTURE_HEIGHT),
+            },
+            self.texture.size(),
+        );
+
+        let used = [
+            columns * CELL_WIDTH + 2 * PADDING,
+            u32::try_from(lines.len()).unwrap_or(LINES) * CELL_HEIGHT + 2 * PADDING,
+        ];
+        let uniforms = HudUniforms {
+            origin: [MARGIN, MARGIN],
+            size: [used[0] as f32 * SCALE, used[1] as f32 * SCALE],
+            screen: [status.resolution[0] as f32, status.resolution[1] as f32],
+            scale: SCALE,
+            _padding: 0.0,
+        };
+        queue.write_buffer(&self.uniforms_buffer, 0, bytemuck::bytes_of(&uniforms));
+
+        let mut renderpass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("hud"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Load,
+                    store: StoreOp::Store,
+                },
+            })],
+            timestamp_writes,
+            ..Default::default()
+        });
+        renderpass.set_pipeline(&self.pipeline);
+        renderpass.set_bind_group(0, &self.bind_group, &[]);
+        renderpass.draw(0..4, 0..1);
+    }
+
+    /// Writes `lines` into the text texture, returning the widest line length.
+    fn rasterize(&mut self, lines: &[String]) -> u32 {
+        for pixel in self.pixels.as_chunks_mut::<4>().0 {
+            *pixel = BACKGROUND;
+        }
+
+        let mut widest = 0;
+        for (row, line) in (0..LINES).zip(lines) {
+            let mut columns = 0;
+            for (column, character) in (0..COLUMNS).zip(line.chars()) {
+                self.blit(glyph(character), column, row);
+                columns = column + 1;
+            }
+            widest = widest.max(columns);
+        }
+        widest
+    }
+
+    fn blit(&mut self, glyph: [u8; 7], column: u32, row: u32) {
+        let left = PADDING + column * CELL_WIDTH;
+        let top = PADDING + row * CELL_HEIGHT;
+
+        for (y, bits) in (0..GLYPH_HEIGHT).zip(glyph) {
+            for x in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - x)) != 0 {
+                    let index = (((top + y) * TEXTURE_WIDTH + left + x) * 4) as usize;
+                    self.pixels[index..index + 4].copy_from_slice(&FOREGROUND);
+                }
+            }
+        }
+    }
+}
+
+fn pipeline(
+    device: &Device,
+    shader: &ShaderModule,
+    layout: &PipelineLayout,
+    format: TextureFormat,
+) -> RenderPipeline {
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("hud_pipeline"),
+        layout: Some(layout),
+        vertex: VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            buffers: &[],
+            compilation_options: PipelineCompilationOptions::default(),
+        },
+        fragment: Some(FragmentState {
+            module: shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(ColorTargetState {
+                format,
+                blend: Some(BlendState::ALPHA_BLENDING),
+                write_mask: ColorWrites::ALL,
+            })],
+            compilation_options: PipelineCompilationOptions::default(),
+        }),
+        primitive: PrimitiveState {
+            topology: PrimitiveTopology::TriangleStrip,
+            ..Default::default()
+        },
+        depth_stencil: None,
+        multisample: MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
+
+/// 5x7 bitmap of `character`, one row per byte with the leftmost pixel in
+/// bit 4. Lowercase letters use the uppercase shapes.
+#[rustfmt::skip]
+fn glyph(character: char) -> [u8; 7] {
+    match character.to_ascii_uppercase() {
+        ' ' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000],
+        '0' => [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110],
+        '1' => [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        '2' => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111],
+        '3' => [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110],
+        '4' => [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010],
+        '5' => [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110],
+        '6' => [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110],
+        '7' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000],
+        '8' => [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110],
+        '9' => [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100],
+        'A' => [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+        'B' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110],
+        'C' => [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110],
+        'D' => [0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100],
+        'E' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111],
+        'F' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000],
+        'G' => [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111],
+        'H' => [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+        'I' => [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        'J' => [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100],
+        'K' => [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001],
+        'L' => [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111],
+        'M' => [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001],
+        'N' => [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001],
+        'O' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+        'P' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000],
+        'Q' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101],
+        'R' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001],
+        'S' => [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110],
+        'T' => [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100],
+        'U' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+        'V' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100],
+        'W' => [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010],
+        'X' => [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001],
+        'Y' => [0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100],
+        'Z' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111],
+        '.' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100],
+        ',' => [0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000],
+        ':' => [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000],
+        '-' => [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000],
+        '_' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b11111],
+        '/' => [0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000],
+        '(' => [0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010],
+        ')' => [0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000],
+        '[' => [0b01110, 0b01000, 0b01000, 0b01000, 0b01000, 0b01000, 0b01110],
+        ']' => [0b01110, 0b00010, 0b00010, 0b00010, 0b00010, 0b00010, 0b01110],
+        '%' => [0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011],
+        '=' => [0b00000, 0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000],
+        '+' => [0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000],
+        '#' => [0b01010, 0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b01010],
+        '!' => [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00100],
+        '\'' => [0b01100, 0b00100, 0b01000, 0b00000, 0b00000, 0b00000, 0b00000],
+        _ => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100],
+    }
+}
@@ -0,0 +1,26 @@
+struct Hud {
+    // Top left corner and size of the overlay, in framebuffer pixels.
+    origin: vec2<f32>,
+    size: vec2<f32>,
+    screen: vec2<f32>,
+    // Framebuffer pixels per texel of the text texture.
+    scale: f32,
+}
+
+@group(0) @binding(0) var<uniform> hud: Hud;
+@group(0) @binding(1) var text: texture_2d<f32>;
+
+// Draws the overlay rectangle as a 4 vertex triangle strip, no vertex buffer.
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let corner = vec2<f32>(f32(index & 1u), f32(index >> 1u));
+    let pixel = hud.origin + corner * hud.size;
+    let ndc = pixel / hud.screen * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0);
+    return vec4<f32>(ndc, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let texel = vec2<i32>((position.xy - hud.origin) / hud.scale);
+    return textureLoad(text, texel, 0);
+}
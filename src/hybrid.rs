@@ -0,0 +1,60 @@
+use crate::gpupipeline::DEPTH_FORMAT;
+use wgpu::{
+    BlendState, ColorTargetState, ColorWrites, CompareFunction, DepthBiasState, DepthStencilState,
+    Device, FragmentState, MultisampleState, PipelineCompilationOptions, PipelineLayout,
+    PrimitiveState, RenderPipeline, RenderPipelineDescriptor, ShaderModule, StencilState,
+    TextureFormat, VertexState, include_wgsl,
+};
+
+/// Name of the fragment entry point that ray marches the SDF part of a scene
+/// with models in it.
+pub const ENTRY_POINT: &str = "fs_raymarch";
+
+/// Pipeline running [`ENTRY_POINT`] of `shader` over the whole frame, after
+/// the model has been drawn into the same color and depth targets.
+///
+/// The entry point takes `@builtin(position)` and writes
+/// `@builtin(frag_depth)` from where its ray hit, projected with the same
+/// `uniforms.view` and `uniforms.projection` as the model, so each hides
+/// whatever is behind it. Rays that hit nothing should be discarded.
+#[must_use]
+pub fn pipeline(
+    device: &Device,
+    shader: &ShaderModule,
+    layout: &PipelineLayout,
+    format: TextureFormat,
+) -> RenderPipeline {
+    let fullscreen = device.create_shader_module(include_wgsl!("fullscreen.wgsl"));
+
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("raymarch_pipeline"),
+        layout: Some(layout),
+        vertex: VertexState {
+            module: &fullscreen,
+            entry_point: Some("vs_main"),
+            buffers: &[],
+            compilation_options: PipelineCompilationOptions::default(),
+        },
+        fragment: Some(FragmentState {
+            module: shader,
+            entry_point: Some(ENTRY_POINT),
+            targets: &[Some(ColorTargetState {
+                format,
+                blend: Some(BlendState::REPLACE),
+                write_mask: ColorWrites::ALL,
+            })],
+            compilation_options: PipelineCompilationOptions::default(),
+        }),
+        primitive: PrimitiveState::default(),
+        depth_stencil: Some(DepthStencilState {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: CompareFunction::Less,
+            stencil: StencilState::default(),
+            bias: DepthBiasState::default(),
+        }),
+        multisample: MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
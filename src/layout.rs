@@ -0,0 +1,459 @@
+use crate::error::SetupError;
+use core::fmt;
+use naga::{ScalarKind, TypeInner, VectorSize};
+
+/// The WGSL scalars host structs are made of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scalar {
+    F32,
+    I32,
+    U32,
+}
+
+/// The WGSL type a host field is uploaded as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Type {
+    Scalar(Scalar),
+    /// `vecN<scalar>`, with N from 2 to 4.
+    Vector(u8, Scalar),
+    /// `matCxR<f32>`, made of `columns` vectors of `rows` floats.
+    Matrix {
+        columns: u8,
+        rows: u8,
+    },
+}
+
+impl Type {
+    /// The byte alignment WGSL gives the type in a uniform buffer.
+    #[must_use]
+    pub fn align(self) -> usize {
+        match self {
+            Type::Scalar(_) => 4,
+            Type::Vector(2, _) => 8,
+            Type::Vector(_, _) => 16,
+            Type::Matrix { rows, .. } => Type::Vector(rows, Scalar::F32).align(),
+        }
+    }
+
+    /// The byte size WGSL gives the type, which for matrices includes the
+    /// padding of each column to its alignment.
+    #[must_use]
+    pub fn size(self) -> usize {
+        match self {
+            Type::Scalar(_) => 4,
+            Type::Vector(size, _) => 4 * usize::from(size),
+            Type::Matrix { columns, rows } => {
+                usize::from(columns) * Type::Vector(rows, Scalar::F32).align()
+            }
+        }
+    }
+
+    /// The type naga made of a WGSL one, `None` for anything a host struct
+    /// can't hold.
+    #[must_use]
+    pub fn from_naga(inner: &TypeInner) -> Option<Type> {
+        let scalar = |scalar: naga::Scalar| match (scalar.kind, scalar.width) {
+            (ScalarKind::Float, 4) => Some(Scalar::F32),
+            (ScalarKind::Sint, 4) => Some(Scalar::I32),
+            (ScalarKind::Uint, 4) => Some(Scalar::U32),
+            _ => None,
+        };
+        let size = |size: VectorSize| size as u8;
+        match *inner {
+            TypeInner::Scalar(kind) => scalar(kind).map(Type::Scalar),
+            TypeInner::Vector {
+                size: vector_size,
+                scalar: kind,
+            } => scalar(kind).map(|kind| Type::Vector(size(vector_size), kind)),
+            TypeInner::Matrix {
+                columns,
+                rows,
+                scalar: kind,
+            } => (scalar(kind) == Some(Scalar::F32)).then(|| Type::Matrix {
+                columns: size(columns),
+                rows: size(rows),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scalar = |scalar| match scalar {
+            Scalar::F32 => "f32",
+            Scalar::I32 => "i32",
+            Scalar::U32 => "u32",
+        };
+        match *self {
+            Type::Scalar(kind) => write!(formatter, "{}", scalar(kind)),
+            Type::Vector(size, kind) => write!(formatter, "vec{size}<{}>", scalar(kind)),
+            Type::Matrix { columns, rows } => write!(formatter, "mat{columns}x{rows}<f32>"),
+        }
+    }
+}
+
+/// A Rust field type with a WGSL counterpart of the same layout.
+pub trait ShaderType {
+    const TYPE: Type;
+}
+
+macro_rules! shader_type {
+    ($($ty:ty => $wgsl:expr),* $(,)?) => {
+        $(impl ShaderType for $ty {
+            const TYPE: Type = $wgsl;
+        })*
+    };
+}
+
+shader_type! {
+    f32 => Type::Scalar(Scalar::F32),
+    i32 => Type::Scalar(Scalar::I32),
+    u32 => Type::Scalar(Scalar::U32),
+    [f32; 2] => Type::Vector(2, Scalar::F32),
+    [f32; 4] => Type::Vector(4, Scalar::F32),
+    [i32; 2] => Type::Vector(2, Scalar::I32),
+    [i32; 4] => Type::Vector(4, Scalar::I32),
+    [u32; 2] => Type::Vector(2, Scalar::U32),
+    [u32; 4] => Type::Vector(4, Scalar::U32),
+    [[f32; 2]; 2] => Type::Matrix { columns: 2, rows: 2 },
+    [[f32; 4]; 4] => Type::Matrix { columns: 4, rows: 4 },
+}
+
+/// A field of a host struct. Fields named with a leading `_` are padding
+/// the shader doesn't see.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Member {
+    pub name: &'static str,
+    pub offset: usize,
+    pub ty: Type,
+}
+
+impl Member {
+    #[must_use]
+    pub fn is_padding(&self) -> bool {
+        self.name.starts_with('_')
+    }
+}
+
+/// A struct uploaded to a uniform buffer as is. Implemented by declaring it
+/// with [`host_struct!`](crate::layout::host_struct).
+pub trait HostStruct: bytemuck::Pod {
+    const MEMBERS: &'static [Member];
+    /// Name of the struct, in Rust and WGSL alike.
+    const NAME: &'static str;
+}
+
+/// Declares a `#[repr(C)]` [`bytemuck::Pod`] struct and implements
+/// [`HostStruct`] for it from its fields, which have to be [`ShaderType`]s.
+macro_rules! host_struct {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($(#[$field_meta:meta])* $field_vis:vis $field:ident: $ty:ty),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[repr(C)]
+        #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+        $vis struct $name {
+            $($(#[$field_meta])* $field_vis $field: $ty),*
+        }
+
+        impl $crate::layout::HostStruct for $name {
+            const MEMBERS: &'static [$crate::layout::Member] = &[$($crate::layout::Member {
+                name: stringify!($field),
+                offset: core::mem::offset_of!($name, $field),
+                ty: <$ty as $crate::layout::ShaderType>::TYPE,
+            }),*];
+            const NAME: &'static str = stringify!($name);
+        }
+    };
+}
+pub(crate) use host_struct;
+
+/// The WGSL declaration of `T`, with members named as its fields. Where `T`
+/// leaves a larger gap than WGSL alignment would, `u32` padding members
+/// named `_padding0`, `_padding1`... fill it, so every member lands at the
+/// offset of its field.
+///
+/// # Panics
+/// If a field of `T` sits before the offset WGSL would give it, which means
+/// `T` itself needs padding.
+#[must_use]
+pub fn wgsl<T: HostStruct>() -> String {
+    let mut lines = Vec::new();
+    let mut end = 0_usize;
+    let mut paddings = 0;
+    for member in T::MEMBERS.iter().filter(|member| !member.is_padding()) {
+        while end.next_multiple_of(member.ty.align()) < member.offset {
+            lines.push(format!("    _padding{paddings}: u32,\n"));
+            paddings += 1;
+            end += 4;
+        }
+        assert_eq!(
+            end.next_multiple_of(member.ty.align()),
+            member.offset,
+            "{}.{} is not aligned as WGSL needs",
+            T::NAME,
+            member.name
+        );
+        lines.push(format!("    {}: {},\n", member.name, member.ty));
+        end = member.offset + member.ty.size();
+    }
+    format!("struct {} {{\n{}}}\n", T::NAME, lines.concat())
+}
+
+/// Checks the uniform buffer `module` declares at `group` and `binding`
+/// against the `T` the host uploads there: each member has to sit at the
+/// offset of a host field of the same type, and all of them inside what is
+/// uploaded. Names don't have to agree, and a shader may leave out fields at
+/// the end. Declaring nothing there is fine too.
+///
+/// # Errors
+/// [`SetupError::UniformLayout`] for the first member that doesn't match.
+pub fn check<T: HostStruct>(
+    name: &str,
+    module: &naga::Module,
+    group: u32,
+    binding: u32,
+) -> Result<(), SetupError> {
+    let Some((_, variable)) = module.global_variables.iter().find(|(_, variable)| {
+        variable.space == naga::AddressSpace::Uniform
+            && variable
+                .binding
+                .as_ref()
+                .is_some_and(|bound| bound.group == group && bound.binding == binding)
+    }) else {
+        return Ok(());
+    };
+    let variable_name = variable.name.as_deref().unwrap_or("uniforms");
+    let error = |reason| SetupError::UniformLayout {
+        name: name.to_owned(),
+        reason,
+    };
+
+    let ty = &module.types[variable.ty];
+    let members: Vec<(String, usize, &TypeInner)> = match &ty.inner {
+        TypeInner::Struct { members, .. } => members
+            .iter()
+            .map(|member| {
+                (
+                    format!("{variable_name}.{}", member.name.as_deref().unwrap_or("_")),
+                    usize::try_from(member.offset).unwrap_or(usize::MAX),
+                    &module.types[member.ty].inner,
+                )
+            })
+            .collect(),
+        inner => vec![(variable_name.to_owned(), 0, inner)],
+    };
+
+    let uploaded = size_of::<T>();
+    for (member, offset, inner) in members {
+        let Some(ty) = Type::from_naga(inner) else {
+            return Err(error(format!("{member} has a type the host never uploads")));
+        };
+        if offset.saturating_add(ty.size()) > uploaded {
+            return Err(error(format!(
+                "{member} ends at byte {}, past the {uploaded} bytes uploaded",
+                offset + ty.size()
+            )));
+        }
+        let host = T::MEMBERS
+            .iter()
+            .find(|host| host.offset <= offset && offset < host.offset + host.ty.size());
+        match host {
+            Some(host) if host.is_padding() => {
+                return Err(error(format!(
+                    "{member} is at byte {offset}, which the host leaves as padding"
+                )));
+            }
+            Some(host) if host.offset == offset && host.ty == ty => {}
+            Some(host) => {
+                return Err(error(format!(
+                    "{member} is a {ty} at byte {offset}, where the host has {}: {} at byte {}",
+                    host.name, host.ty, host.offset
+                )));
+            }
+            None => {
+                return Err(error(format!(
+                    "{member} is at byte {offset}, which the host leaves as padding"
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpupipeline::Uniforms;
+
+    host_struct! {
+        struct Probe {
+            position: [f32; 2],
+            scale: f32,
+            _padding: f32,
+            color: [f32; 4],
+        }
+    }
+
+    fn check_wgsl(code: &str) -> Result<(), String> {
+        let module = naga::front::wgsl::parse_str(code).expect("the snippet parses");
+        check::<Probe>("probe.wgsl", &module, 0, 0).map_err(|error| error.to_string())
+    }
+
+    #[test]
+    fn a_matching_prefix_is_fine() {
+        let code = "
+            struct Probe { position: vec2<f32>, size: f32 }
+            @group(0) @binding(0) var<uniform> probe: Probe;
+        ";
+        assert_eq!(check_wgsl(code), Ok(()));
+        assert_eq!(
+            check_wgsl("@group(0) @binding(0) var<uniform> position: vec2<f32>;"),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn host_padding_is_not_a_member() {
+        let code = "
+            struct Probe { position: vec2<f32>, scale: f32, extra: f32 }
+            @group(0) @binding(0) var<uniform> probe: Probe;
+        ";
+        let error = check_wgsl(code).unwrap_err();
+        assert!(error.contains("probe.extra is at byte 12"), "{error}");
+        assert!(error.contains("padding"), "{error}");
+    }
+
+    #[test]
+    fn types_have_to_match() {
+        let code = "
+            struct Probe { position: vec2<f32>, scale: u32 }
+            @group(0) @binding(0) var<uniform> probe: Probe;
+        ";
+        let error = check_wgsl(code).unwrap_err();
+        assert!(
+            error.contains("probe.scale is a u32 at byte 8, where the host has scale: f32"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn members_past_the_upload_are_rejected() {
+        let code = "
+            struct Probe {
+                position: vec2<f32>,
+                scale: f32,
+                @align(16) color: vec4<f32>,
+                alpha: f32,
+            }
+            @group(0) @binding(0) var<uniform> probe: Probe;
+        ";
+        let error = check_wgsl(code).unwrap_err();
+        assert!(
+            error.contains("ends at byte 36, past the 32 bytes"),
+            "{error}"
+        );
+    }
+
+    /// Name and offset of each member naga gives the struct `name` in
+    /// `code`, padding left out.
+    fn naga_offsets(code: &str, name: &str) -> Vec<(String, usize)> {
+        let module = naga::front::wgsl::parse_str(code).expect("the generated struct parses");
+        let (_, ty) = module
+            .types
+            .iter()
+            .find(|(_, ty)| ty.name.as_deref() == Some(name))
+            .expect("the struct is declared");
+        let naga::TypeInner::Struct { members, .. } = &ty.inner else {
+            panic!("{name} is a struct");
+        };
+        members
+            .iter()
+            .filter_map(|member| {
+                let member_name = member.name.clone()?;
+                let offset = usize::try_from(member.offset).ok()?;
+                (!member_name.starts_with('_')).then_some((member_name, offset))
+            })
+            .collect()
+    }
+
+    fn host_offsets<T: HostStruct>() -> Vec<(String, usize)> {
+        T::MEMBERS
+            .iter()
+            .filter(|member| !member.is_padding())
+            .map(|member| (member.name.to_owned(), member.offset))
+            .collect()
+    }
+
+    #[test]
+    fn generated_wgsl_is_pinned() {
+        assert_eq!(
+            wgsl::<Probe>(),
+            "struct Probe {\n    position: vec2<f32>,\n    scale: f32,\n    color: vec4<f32>,\n}\n"
+        );
+    }
+
+    #[test]
+    fn generated_wgsl_puts_members_at_host_offsets() {
+        assert_eq!(
+            naga_offsets(&wgsl::<Probe>(), "Probe"),
+            host_offsets::<Probe>()
+        );
+        assert_eq!(
+            naga_offsets(&wgsl::<Uniforms>(), "Uniforms"),
+            host_offsets::<Uniforms>()
+        );
+    }
+
+    #[test]
+    fn mat3x3_columns_are_padded_like_naga_does() {
+        let module = naga::front::wgsl::parse_str(
+            "struct Padded { basis: mat3x3<f32>, after: f32 }
+            @group(0) @binding(0) var<uniform> padded: Padded;",
+        )
+        .expect("the snippet parses");
+        let (_, ty) = module
+            .types
+            .iter()
+            .find(|(_, ty)| ty.name.as_deref() == Some("Padded"))
+            .expect("the struct is declared");
+        let naga::TypeInner::Struct { members, .. } = &ty.inner else {
+            panic!("Padded is a struct");
+        };
+
+        let basis = Type::from_naga(&module.types[members[0].ty].inner);
+        assert_eq!(
+            basis,
+            Some(Type::Matrix {
+                columns: 3,
+                rows: 3
+            })
+        );
+        let basis = basis.expect("mat3x3 has a host type");
+        assert_eq!(basis.align(), 16);
+        assert_eq!(basis.size(), 48);
+        assert_eq!(members[1].offset, 48);
+    }
+
+    #[test]
+    fn sizes_and_alignments_follow_wgsl() {
+        let vec3 = Type::Vector(3, Scalar::F32);
+        assert_eq!((vec3.size(), vec3.align()), (12, 16));
+        let vec2 = Type::Vector(2, Scalar::U32);
+        assert_eq!((vec2.size(), vec2.align()), (8, 8));
+        let mat2 = Type::Matrix {
+            columns: 2,
+            rows: 2,
+        };
+        assert_eq!((mat2.size(), mat2.align()), (16, 8));
+        let mat4 = Type::Matrix {
+            columns: 4,
+            rows: 4,
+        };
+        assert_eq!((mat4.size(), mat4.align()), (64, 16));
+    }
+}
@@ -0,0 +1,36 @@
+// Default shader for models: Lambert shading from one directional light.
+
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+
+const LIGHT_DIRECTION = vec3<f32>(0.4, 0.8, 0.45);
+const ALBEDO = vec3<f32>(0.8, 0.8, 0.8);
+const AMBIENT = 0.15;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) normal: vec3<f32>,
+    @location(1) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    let world = uniforms.model * vec4<f32>(in.position, 1.0);
+    var out: VertexOutput;
+    out.clip_position = uniforms.projection * uniforms.view * world;
+    // the model matrix only scales uniformly, so it transforms normals too
+    out.normal = (uniforms.model * vec4<f32>(in.normal, 0.0)).xyz;
+    out.uv = in.uv;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let diffuse = max(dot(normalize(in.normal), normalize(LIGHT_DIRECTION)), 0.0);
+    return vec4<f32>(ALBEDO * (AMBIENT + diffuse), 1.0);
+}
@@ -13,26 +13,123 @@
     reason = "i want to do it as it says"
 )]
 
-use app::App;
+use app::{App, WindowSpec};
+use cli::Command;
 use core::error::Error;
+use std::env;
+use std::process::ExitCode;
 use tracing::{Level, span};
 use winit::event_loop::EventLoop;
 
 pub mod app;
+pub mod bench;
+pub mod camera;
+pub mod cli;
+pub mod clock;
+pub mod color;
+pub mod compare;
+pub mod compute;
 pub mod config;
+pub mod device_watch;
+pub mod downsample;
+pub mod error;
+pub mod golden;
 pub mod gpupipeline;
+pub mod hud;
+pub mod hybrid;
+pub mod layout;
 pub mod model;
+pub mod offline;
+pub mod profiler;
+pub mod reflect;
+pub mod shader;
+pub mod storage;
+pub mod tonemap;
+pub mod vertex;
 
-fn main() -> Result<(), Box<dyn Error>> {
+fn main() -> ExitCode {
     tracing_subscriber::fmt::init();
     let span = span!(Level::WARN, "MAIN_THREAD").entered();
+    let result = run();
+    span.exit();
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
 
-    let event_loop = EventLoop::new().unwrap();
+fn run() -> Result<(), Box<dyn Error>> {
+    let windows = match cli::parse(env::args().skip(1))? {
+        Command::Window {
+            shaders,
+            model,
+            vertices,
+            topology,
+            tonemap,
+            exposure,
+        } => {
+            let spec = WindowSpec {
+                model,
+                vertices,
+                topology,
+                tonemap,
+                exposure,
+                ..WindowSpec::default()
+            };
+            if shaders.is_empty() {
+                vec![spec]
+            } else {
+                shaders
+                    .into_iter()
+                    .map(|shader| WindowSpec {
+                        shader: Some(shader),
+                        ..spec.clone()
+                    })
+                    .collect()
+            }
+        }
+        Command::Compare { a, b } => vec![WindowSpec {
+            shader: Some(a),
+            compare: Some(b),
+            ..WindowSpec::default()
+        }],
+        Command::Export(options) => return offline::export(&options),
+        Command::Golden(options) => {
+            let checks = golden::run(&options)?;
+            for check in &checks {
+                println!(
+                    "{} t={}: {:?}",
+                    check.reference.display(),
+                    check.time,
+                    check.outcome
+                );
+            }
+
+            let failed = checks.iter().filter(|check| !check.passed()).count();
+            if failed > 0 {
+                return Err(format!("{failed} golden image(s) failed").into());
+            }
+            return Ok(());
+        }
+        Command::Bench(options) => {
+            let report = bench::run(&options)?;
+            println!("{}", report.to_json(&options));
+            return Ok(());
+        }
+    };
+
+    let event_loop = EventLoop::new()?;
     event_loop.set_control_flow(winit::event_loop::ControlFlow::Poll);
 
-    let mut app = App { componet: None };
-    let _ = event_loop.run_app(&mut app);
+    let mut app = App::new(windows);
+    event_loop.run_app(&mut app)?;
 
-    span.exit();
-    Ok(())
+    match app.error {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
 }
@@ -1,8 +1,21 @@
-#[repr(C)]
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-pub struct Vertex {
-    position: [f32; 3],
-    color: [f32; 3],
+use crate::color::Srgb;
+use crate::error::SetupError;
+use crate::vertex::{VertexLayout as _, vertex};
+use cgmath::{Matrix as _, Matrix4, Point3, SquareMatrix as _, Transform as _, Vector3};
+use core::error::Error;
+use core::f32::consts::{PI, TAU};
+use core::str::FromStr;
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::Path;
+use wgpu::VertexBufferLayout;
+
+vertex! {
+    #[derive(Debug)]
+    pub struct Vertex {
+        position: [f32; 3],
+        color: [f32; 3],
+    }
 }
 
 pub const TRIANGLE: [Vertex; 3] = [
@@ -20,121 +33,655 @@ pub const TRIANGLE: [Vertex; 3] = [
     },
 ];
 
-fn to_linear(mut color: [f32; 3]) -> [f32; 3] {
-    (0..color.len()).for_each(|c| {
-        color[c] = ((color[c] + 0.055) / 1.055).powf(2.4);
-    });
-
-    color
-}
-
+/// The fullscreen quad, with a muted color in each corner.
+#[must_use]
 pub fn vertices() -> [Vertex; 4] {
     [
         Vertex {
             position: [-1.0, 1.0, 0.0],
-            color: to_linear([0.28235, 0.18039, 0.0745098]),
+            color: Srgb::hex(0x48_2e_13).to_linear().0,
         },
         Vertex {
             position: [1.0, 1.0, 0.0],
-            color: to_linear([0.3568, 0.290196, 0.211764]),
+            color: Srgb::hex(0x5b_4a_36).to_linear().0,
         },
         Vertex {
             position: [-1.0, -1.0, 0.0],
-            color: to_linear([0.1333, 0.1843, 0.2980]),
+            color: Srgb::hex(0x22_2f_4c).to_linear().0,
         },
         Vertex {
             position: [1.0, -1.0, 0.0],
-            color: to_linear([0.6196, 0.54509, 0.43529]),
+            color: Srgb::hex(0x9e_8b_6f).to_linear().0,
         },
     ]
 }
 
-pub const INDICES: &[u16] = &[2, 1, 0, 3, 1, 2];
-
-pub const CUBE: &[Vertex] = &[];
-pub const SPHERE: &[Vertex] = &[];
-
-/*
-# Graphics Programming Discussion - RGB, sRGB, and Color Spaces
-
-## RGB vs sRGB Fundamentals
-
-**RGB** is a color model - a way to represent colors using Red, Green, Blue components. It doesn't specify what those numbers mean in terms of actual light.
-
-**Color spaces** define what RGB numbers represent. Examples:
-- **sRGB** (monitors/web)
-- **Adobe RGB** (professional photography)
-- **Linear RGB** (mathematically linear light values)
-
-**sRGB** is a specific RGB color space designed to match human brightness perception. Our eyes don't perceive light linearly - we're more sensitive to changes in dark colors than bright colors.
+pub const INDICES: &[u32] = &[2, 1, 0, 3, 1, 2];
+
+/// What the vertex stage is fed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Geometry {
+    /// The fullscreen quad of [`vertices`] and [`INDICES`], for fragment
+    /// shaders.
+    #[default]
+    Quad,
+    /// A loaded or generated mesh, drawn with the [`MeshVertex`] attributes.
+    Mesh(Mesh),
+    /// `count` vertices and no vertex buffer, placed by the vertex shader
+    /// from `@builtin(vertex_index)` alone.
+    Vertices { count: u32, topology: Topology },
+}
 
-## The wgpu Tutorial Problem
+impl Geometry {
+    #[must_use]
+    pub fn mesh(&self) -> Option<&Mesh> {
+        match self {
+            Geometry::Quad | Geometry::Vertices { .. } => None,
+            Geometry::Mesh(mesh) => Some(mesh),
+        }
+    }
+
+    /// What bare vertices make up, `None` for indexed triangles.
+    #[must_use]
+    pub fn topology(&self) -> Option<Topology> {
+        match self {
+            Geometry::Quad | Geometry::Mesh(_) => None,
+            Geometry::Vertices { topology, .. } => Some(*topology),
+        }
+    }
+
+    /// The vertex buffer layout, `None` for bare vertices which have no
+    /// vertex buffer.
+    #[must_use]
+    pub fn vertex_layout(&self) -> Option<VertexBufferLayout<'static>> {
+        match self {
+            Geometry::Quad => Some(Vertex::layout()),
+            Geometry::Mesh(_) => Some(MeshVertex::layout()),
+            Geometry::Vertices { .. } => None,
+        }
+    }
+
+    /// How many vertices are drawn, handed to the shader as
+    /// `uniforms.vertex_count`.
+    #[must_use]
+    pub fn vertex_count(&self) -> u32 {
+        match self {
+            Geometry::Quad => 4,
+            Geometry::Mesh(mesh) => u32::try_from(mesh.vertices.len()).unwrap_or(u32::MAX),
+            Geometry::Vertices { count, .. } => *count,
+        }
+    }
+}
 
-Your vertex colors: `(0.5, 0.0, 0.5)` (linear RGB)
-→ GPU converts for sRGB display
-→ Results in hex `#BC00BC` = `(188, 0, 188)` = `(0.737, 0, 0.737)` when measured
+/// What the vertices of [`Geometry::Vertices`] make up.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Topology {
+    #[default]
+    Points,
+    /// Every two vertices a separate line.
+    Lines,
+    /// Every three vertices a separate triangle, visible from both sides.
+    Triangles,
+}
 
-The mismatch occurs because of gamma correction during linear → sRGB conversion.
+impl FromStr for Topology {
+    type Err = Box<dyn Error>;
+
+    fn from_str(name: &str) -> Result<Topology, Self::Err> {
+        match name {
+            "points" => Ok(Topology::Points),
+            "lines" => Ok(Topology::Lines),
+            "triangles" => Ok(Topology::Triangles),
+            _ => Err(
+                format!("unknown topology `{name}`, expected points, lines or triangles").into(),
+            ),
+        }
+    }
+}
 
-## Gamma Correction Deep Dive
+vertex! {
+    /// A vertex of the generated and loaded meshes, with what a shader needs to light and
+    /// texture them.
+    #[derive(Debug, PartialEq)]
+    pub struct MeshVertex {
+        pub position: [f32; 3],
+        pub normal: [f32; 3],
+        pub uv: [f32; 2],
+    }
+}
 
-### Historical Origin
-CRT monitors had non-linear voltage-to-light response: `light_output = voltage^2.2`
-To get desired brightness, engineers pre-corrected signals: `corrected_voltage = desired_brightness^(1/2.2)`
+/// Triangles listed counter-clockwise seen from outside, the front face
+/// `Conf::primitives()` keeps.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mesh {
+    pub vertices: Vec<MeshVertex>,
+    pub indices: Vec<u32>,
+}
 
-### What Gamma Correction Does
-Redistributes brightness levels using power functions:
-```
-gamma_corrected = linear^(1/gamma)
-linear = gamma_corrected^gamma
-```
-Where gamma ≈ 2.2 for displays.
+impl Mesh {
+    /// Appends a vertex and returns its index.
+    fn push(&mut self, position: [f32; 3], normal: [f32; 3], uv: [f32; 2]) -> u32 {
+        let index = u32::try_from(self.vertices.len()).expect("mesh fits u32 indices");
+        self.vertices.push(MeshVertex {
+            position,
+            normal,
+            uv,
+        });
+        index
+    }
+
+    /// The model matrix that centers the mesh on the origin and scales it
+    /// to fit in the unit sphere, so any model fills the default camera.
+    #[must_use]
+    pub fn fit(&self) -> Matrix4<f32> {
+        let mut min = [f32::INFINITY; 3];
+        let mut max = [f32::NEG_INFINITY; 3];
+        for vertex in &self.vertices {
+            min = [0, 1, 2].map(|axis| min[axis].min(vertex.position[axis]));
+            max = [0, 1, 2].map(|axis| max[axis].max(vertex.position[axis]));
+        }
+        let center: [f32; 3] = [0, 1, 2].map(|axis| f32::midpoint(min[axis], max[axis]));
+
+        let radius = self
+            .vertices
+            .iter()
+            .map(|vertex| {
+                let offset = [0, 1, 2].map(|axis| vertex.position[axis] - center[axis]);
+                offset.iter().map(|axis| axis * axis).sum::<f32>()
+            })
+            .fold(0.0, f32::max)
+            .sqrt();
+        if radius <= 0.0 {
+            return Matrix4::identity();
+        }
+
+        Matrix4::from_scale(radius.recip()) * Matrix4::from_translation(-Vector3::from(center))
+    }
+
+    /// Gives the vertices without a normal the average of the faces around
+    /// them, weighted by area.
+    fn fill_normals(&mut self) {
+        let mut sums = vec![[0.0_f32; 3]; self.vertices.len()];
+        for triangle in self.indices.as_chunks::<3>().0 {
+            let [a, b, c] =
+                [0, 1, 2].map(|corner| self.vertices[triangle[corner] as usize].position);
+            let normal = cross(
+                [0, 1, 2].map(|axis| b[axis] - a[axis]),
+                [0, 1, 2].map(|axis| c[axis] - a[axis]),
+            );
+            for &index in triangle {
+                let sum = &mut sums[index as usize];
+                *sum = [0, 1, 2].map(|axis| sum[axis] + normal[axis]);
+            }
+        }
+
+        for (vertex, sum) in self.vertices.iter_mut().zip(sums) {
+            if vertex.normal.iter().all(|&axis| axis == 0.0) {
+                vertex.normal = normalize(sum);
+            }
+        }
+    }
+
+    /// Appends a `columns` by `rows` grid of quads, with `surface` giving the
+    /// position and normal at each `(s, t)` in `[0, 1]`, which are also the
+    /// UVs. Like in a texture, `s` goes right and `t` down as seen from the
+    /// front.
+    fn grid(
+        &mut self,
+        columns: u32,
+        rows: u32,
+        surface: impl Fn(f32, f32) -> ([f32; 3], [f32; 3]),
+    ) {
+        let columns = columns.max(1);
+        let rows = rows.max(1);
+        let first = u32::try_from(self.vertices.len()).expect("mesh fits u32 indices");
+
+        for row in 0..=rows {
+            for column in 0..=columns {
+                let uv = [fraction(column, columns), fraction(row, rows)];
+                let (position, normal) = surface(uv[0], uv[1]);
+                self.push(position, normal, uv);
+            }
+        }
+
+        for row in 0..rows {
+            for column in 0..columns {
+                let corner = first + row * (columns + 1) + column;
+                let below = corner + columns + 1;
+                self.indices
+                    .extend([corner, below, below + 1, corner, below + 1, corner + 1]);
+            }
+        }
+    }
+}
 
-### Visual Effect
-**Linear gradient** 0.0, 0.25, 0.5, 0.75, 1.0 appears unevenly spaced to human eyes
-**Gamma corrected** 0.0, 0.537, 0.735, 0.881, 1.0 appears evenly spaced
+/// A cube centered on the origin, `size` along each side, with its own
+/// vertices per face so the normals stay flat.
+#[must_use]
+pub fn cube(size: f32) -> Mesh {
+    // normal, then two axes along the face whose cross product is the normal
+    const FACES: [[[f32; 3]; 3]; 6] = [
+        [[1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]],
+        [[-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]],
+        [[0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]],
+        [[0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]],
+        [[0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+        [[0.0, 0.0, -1.0], [-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+    ];
+
+    let half = size / 2.0;
+    let mut mesh = Mesh::default();
+    for [normal, across, up] in FACES {
+        mesh.grid(1, 1, |s, t| {
+            let (x, y) = (s * 2.0 - 1.0, 1.0 - t * 2.0);
+            let position =
+                [0, 1, 2].map(|axis| (normal[axis] + across[axis] * x + up[axis] * y) * half);
+            (position, normal)
+        });
+    }
+    mesh
+}
 
-### sRGB Formula
-```
-// sRGB to Linear
-linear = ((sRGB + 0.055) / 1.055)^2.4
+/// A sphere of `radius` made of `segments` slices around the Y axis and
+/// `rings` stacks from pole to pole.
+#[must_use]
+pub fn uv_sphere(radius: f32, segments: u32, rings: u32) -> Mesh {
+    let mut mesh = Mesh::default();
+    mesh.grid(segments.max(3), rings.max(2), |s, t| {
+        let normal = spherical(s * TAU, t * PI);
+        (normal.map(|axis| axis * radius), normal)
+    });
+    mesh
+}
 
-// Linear to sRGB
-sRGB = 1.055 * linear^(1/2.4) - 0.055
-```
+/// A sphere of `radius` made by splitting each triangle of an icosahedron
+/// into four, `subdivisions` times, for evenly sized triangles.
+///
+/// The UVs are spherical like [`uv_sphere`]'s, and wrap around at the seam
+/// without duplicated vertices.
+///
+/// # Panics
+/// Past 14 subdivisions, when the vertices no longer fit `u32` indices.
+#[must_use]
+pub fn icosphere(radius: f32, subdivisions: u32) -> Mesh {
+    let golden = f32::midpoint(1.0, 5.0_f32.sqrt());
+    let mut corners = vec![
+        [-1.0, golden, 0.0],
+        [1.0, golden, 0.0],
+        [-1.0, -golden, 0.0],
+        [1.0, -golden, 0.0],
+        [0.0, -1.0, golden],
+        [0.0, 1.0, golden],
+        [0.0, -1.0, -golden],
+        [0.0, 1.0, -golden],
+        [golden, 0.0, -1.0],
+        [golden, 0.0, 1.0],
+        [-golden, 0.0, -1.0],
+        [-golden, 0.0, 1.0],
+    ]
+    .into_iter()
+    .map(normalize)
+    .collect::<Vec<_>>();
+    let mut triangles: Vec<[u32; 3]> = vec![
+        [0, 11, 5],
+        [0, 5, 1],
+        [0, 1, 7],
+        [0, 7, 10],
+        [0, 10, 11],
+        [1, 5, 9],
+        [5, 11, 4],
+        [11, 10, 2],
+        [10, 7, 6],
+        [7, 1, 8],
+        [3, 9, 4],
+        [3, 4, 2],
+        [3, 2, 6],
+        [3, 6, 8],
+        [3, 8, 9],
+        [4, 9, 5],
+        [2, 4, 11],
+        [6, 2, 10],
+        [8, 6, 7],
+        [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions {
+        let mut midpoints = HashMap::new();
+        let mut midpoint = |a: u32, b: u32| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                let [from, to] = [a, b].map(|index| corners[index as usize]);
+                corners.push(normalize([0, 1, 2].map(|axis| from[axis] + to[axis])));
+                u32::try_from(corners.len() - 1).expect("mesh fits u32 indices")
+            })
+        };
+        triangles = triangles
+            .into_iter()
+            .flat_map(|[a, b, c]| {
+                let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+                [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+            })
+            .collect();
+    }
+
+    let mut mesh = Mesh::default();
+    for normal in corners {
+        let uv = [
+            (-normal[2]).atan2(normal[0]).rem_euclid(TAU) / TAU,
+            normal[1].clamp(-1.0, 1.0).acos() / PI,
+        ];
+        mesh.push(normal.map(|axis| axis * radius), normal, uv);
+    }
+    mesh.indices = triangles.into_iter().flatten().collect();
+    mesh
+}
 
-## Matrix Transformations vs Gamma Correction
+/// A square in the XZ plane facing up, `size` along each side and split
+/// into `subdivisions` quads per side for vertex displacement.
+#[must_use]
+pub fn plane(size: f32, subdivisions: u32) -> Mesh {
+    let mut mesh = Mesh::default();
+    mesh.grid(subdivisions, subdivisions, |s, t| {
+        ([(s - 0.5) * size, 0.0, (t - 0.5) * size], [0.0, 1.0, 0.0])
+    });
+    mesh
+}
 
-**Gamma correction** (sRGB ↔ Linear): Non-linear per-channel transformation using power functions
+/// A torus around the Y axis, `major_radius` from its center to the middle
+/// of the tube and `minor_radius` across the tube.
+#[must_use]
+pub fn torus(major_radius: f32, minor_radius: f32, segments: u32, sides: u32) -> Mesh {
+    let mut mesh = Mesh::default();
+    mesh.grid(segments.max(3), sides.max(3), |s, t| {
+        let normal = spherical(s * TAU, t * TAU);
+        let (sin, cos) = (s * TAU).sin_cos();
+        let center = [cos * major_radius, 0.0, -sin * major_radius];
+        let position = [0, 1, 2].map(|axis| center[axis] + normal[axis] * minor_radius);
+        (position, normal)
+    });
+    mesh
+}
 
-**Color space conversion** (different RGB primaries): Matrix multiplication
-```
-[R_new]   [m11 m12 m13] [R_old]
-[G_new] = [m21 m22 m23] [G_old]
-[B_new]   [m31 m32 m33] [B_old]
-```
+/// A capped cylinder along the Y axis, centered on the origin.
+#[must_use]
+pub fn cylinder(radius: f32, height: f32, segments: u32) -> Mesh {
+    let segments = segments.max(3);
+    let mut mesh = Mesh::default();
+    mesh.grid(segments, 1, |s, t| {
+        let (sin, cos) = (s * TAU).sin_cos();
+        (
+            [cos * radius, (0.5 - t) * height, -sin * radius],
+            [cos, 0.0, -sin],
+        )
+    });
+    // caps as rings shrinking to their center
+    for (y, top) in [(height / 2.0, true), (-height / 2.0, false)] {
+        mesh.grid(segments, 1, |s, t| {
+            let distance = if top { t } else { 1.0 - t } * radius;
+            let (sin, cos) = (s * TAU).sin_cos();
+            ([cos * distance, y, -sin * distance], [0.0, y.signum(), 0.0])
+        });
+    }
+    mesh
+}
 
-Your tutorial case uses only gamma correction, not matrix operations.
+/// One of the generated meshes by name: `cube`, `sphere`, `icosphere`,
+/// `plane`, `torus` or `cylinder`.
+#[must_use]
+pub fn builtin(name: &str) -> Option<Mesh> {
+    Some(match name {
+        "cube" => cube(1.0),
+        "sphere" => uv_sphere(1.0, 48, 24),
+        "icosphere" => icosphere(1.0, 3),
+        "plane" => plane(2.0, 32),
+        "torus" => torus(1.0, 0.35, 64, 24),
+        "cylinder" => cylinder(0.5, 1.5, 48),
+        _ => return None,
+    })
+}
 
-## Human Visual System Limitations
+/// Reads a `.obj`, `.gltf` or `.glb` file into one mesh, or generates one
+/// when `path` is the name of a [`builtin`] one. Normals are computed for
+/// vertices that have none, missing UVs are zero.
+///
+/// # Errors
+/// [`SetupError::LoadModel`] when the file can't be read or parsed, or has
+/// no triangles.
+pub fn load(path: &Path) -> Result<Mesh, SetupError> {
+    if let Some(mesh) = path.to_str().and_then(builtin) {
+        return Ok(mesh);
+    }
+
+    let error = |source| SetupError::LoadModel {
+        path: path.to_path_buf(),
+        source,
+    };
+
+    let extension = path
+        .extension()
+        .and_then(OsStr::to_str)
+        .map(str::to_ascii_lowercase);
+    let mut mesh = match extension.as_deref() {
+        Some("obj") => load_obj(path),
+        Some("gltf" | "glb") => load_gltf(path),
+        _ => Err("expected a .obj, .gltf or .glb file".into()),
+    }
+    .map_err(error)?;
+
+    if mesh.indices.is_empty() {
+        return Err(error("no triangles in it".into()));
+    }
+    mesh.fill_normals();
+    Ok(mesh)
+}
 
-**Luminance sensitivity**:
-- Green: ~59% of perceived brightness
-- Red: ~30%
-- Blue: ~11%
+/// Every object in the file, triangulated.
+fn load_obj(path: &Path) -> Result<Mesh, Box<dyn Error + Send + Sync>> {
+    // materials aren't used, so a missing .mtl file doesn't matter
+    let (models, _materials) = tobj::load_obj(path, &tobj::GPU_LOAD_OPTIONS)?;
+
+    let mut mesh = Mesh::default();
+    for model in models {
+        let obj = model.mesh;
+        let first = u32::try_from(mesh.vertices.len())?;
+        for (vertex, position) in obj.positions.as_chunks::<3>().0.iter().enumerate() {
+            let normal = obj
+                .normals
+                .get(vertex * 3..vertex * 3 + 3)
+                .map_or([0.0; 3], |normal| [normal[0], normal[1], normal[2]]);
+            // OBJ puts v = 0 at the bottom, wgpu at the top
+            let uv = obj
+                .texcoords
+                .get(vertex * 2..vertex * 2 + 2)
+                .map_or([0.0; 2], |uv| [uv[0], 1.0 - uv[1]]);
+            mesh.push(*position, normal, uv);
+        }
+        mesh.indices
+            .extend(obj.indices.into_iter().map(|index| first + index));
+    }
+    Ok(mesh)
+}
 
-**Why graphics ignores this**: Historical pragmatism, "good enough" results, established workflows would break if changed.
+/// The triangles of the default scene, with the node transforms applied.
+fn load_gltf(path: &Path) -> Result<Mesh, Box<dyn Error + Send + Sync>> {
+    let (document, buffers, _images) = gltf::import(path)?;
+    let scene = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+        .ok_or("no scene in it")?;
+
+    let mut mesh = Mesh::default();
+    let mut nodes: Vec<_> = scene
+        .nodes()
+        .map(|node| (node, Matrix4::identity()))
+        .collect();
+    while let Some((node, parent)) = nodes.pop() {
+        let transform = parent * Matrix4::from(node.transform().matrix());
+        nodes.extend(node.children().map(|child| (child, transform)));
+        let Some(node_mesh) = node.mesh() else {
+            continue;
+        };
+        let normal_transform = transform.invert().unwrap_or(transform).transpose();
+
+        for primitive in node_mesh.primitives() {
+            if primitive.mode() != gltf::mesh::Mode::Triangles {
+                continue;
+            }
+            let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+            let Some(positions) = reader.read_positions() else {
+                continue;
+            };
+            let normals: Vec<_> = reader.read_normals().into_iter().flatten().collect();
+            let uvs: Vec<_> = reader
+                .read_tex_coords(0)
+                .into_iter()
+                .flat_map(gltf::mesh::util::ReadTexCoords::into_f32)
+                .collect();
+
+            let first = u32::try_from(mesh.vertices.len())?;
+            for (vertex, position) in positions.enumerate() {
+                let normal = normals.get(vertex).map_or([0.0; 3], |&normal| {
+                    normalize(
+                        normal_transform
+                            .transform_vector(Vector3::from(normal))
+                            .into(),
+                    )
+                });
+                mesh.push(
+                    transform.transform_point(Point3::from(position)).into(),
+                    normal,
+                    uvs.get(vertex).copied().unwrap_or_default(),
+                );
+            }
+
+            let count = u32::try_from(mesh.vertices.len())? - first;
+            match reader.read_indices() {
+                Some(indices) => mesh
+                    .indices
+                    .extend(indices.into_u32().map(|index| first + index)),
+                None => mesh.indices.extend(first..first + count),
+            }
+        }
+    }
+    Ok(mesh)
+}
 
-**Where it IS considered**:
-- Video compression (Y/Cb/Cr channels)
-- Advanced color spaces (LAB, Oklab)
-- Professional image processing
+/// `step / steps` as a float.
+#[expect(clippy::cast_precision_loss, reason = "mesh resolutions are small")]
+fn fraction(step: u32, steps: u32) -> f32 {
+    step as f32 / steps as f32
+}
 
-## Oklab Color Space
+/// The unit vector at `longitude` counter-clockwise around the Y axis from
+/// +X, seen from above, and `colatitude` down from +Y.
+fn spherical(longitude: f32, colatitude: f32) -> [f32; 3] {
+    let (sin_longitude, cos_longitude) = longitude.sin_cos();
+    let (sin_colatitude, cos_colatitude) = colatitude.sin_cos();
+    [
+        sin_colatitude * cos_longitude,
+        cos_colatitude,
+        -sin_colatitude * sin_longitude,
+    ]
+}
 
-Modern perceptually uniform color space (2020) with better hue preservation and mathematical elegance.
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
 
-**For your current situation**: Won't help with gamma correction issue. You'd still need Linear RGB for GPU and gamma correction for display.
+/// `vector` scaled to unit length, or left as is when it has none.
+fn normalize(vector: [f32; 3]) -> [f32; 3] {
+    let length = vector.iter().map(|axis| axis * axis).sum::<f32>().sqrt();
+    if length > 0.0 {
+        vector.map(|axis| axis / length)
+    } else {
+        vector
+    }
+}
 
-**Where Oklab excels**: Color manipulation, gradients, palette generation - but adds complexity for real-time graphics.
-*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every generator at a small size, with the vertex and index counts its
+    /// segments should give. Grids of `c` by `r` quads have `(c + 1) * (r + 1)`
+    /// vertices and two triangles per quad.
+    fn generated() -> Vec<(&'static str, Mesh, usize, usize)> {
+        vec![
+            ("cube", cube(2.0), 6 * 4, 6 * 6),
+            ("uv_sphere", uv_sphere(1.5, 8, 5), 9 * 6, 6 * 8 * 5),
+            ("icosphere", icosphere(1.0, 0), 12, 3 * 20),
+            ("icosphere", icosphere(1.0, 2), 10 * 16 + 2, 3 * 20 * 16),
+            ("plane", plane(2.0, 4), 5 * 5, 6 * 4 * 4),
+            ("torus", torus(1.0, 0.25, 12, 6), 13 * 7, 6 * 12 * 6),
+            ("cylinder", cylinder(0.5, 2.0, 10), 3 * 11 * 2, 3 * 6 * 10),
+        ]
+    }
+
+    /// The small meshes of [`generated`] and the [`builtin`] ones.
+    fn meshes() -> Vec<(&'static str, Mesh)> {
+        let builtins = ["cube", "sphere", "icosphere", "plane", "torus", "cylinder"]
+            .map(|name| (name, builtin(name).expect("the name is a builtin mesh")));
+        generated()
+            .into_iter()
+            .map(|(name, mesh, _, _)| (name, mesh))
+            .chain(builtins)
+            .collect()
+    }
+
+    fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+        (0..3).map(|axis| a[axis] * b[axis]).sum()
+    }
+
+    #[test]
+    fn counts_follow_the_segments() {
+        for (name, mesh, vertices, indices) in generated() {
+            assert_eq!(mesh.vertices.len(), vertices, "{name} vertices");
+            assert_eq!(mesh.indices.len(), indices, "{name} indices");
+        }
+    }
+
+    #[test]
+    fn indices_are_in_range() {
+        for (name, mesh) in meshes() {
+            let count = u32::try_from(mesh.vertices.len()).expect("mesh fits u32 indices");
+            assert!(mesh.indices.iter().all(|&index| index < count), "{name}");
+            assert_eq!(mesh.indices.len() % 3, 0, "{name} has whole triangles");
+        }
+    }
+
+    #[test]
+    fn normals_have_unit_length() {
+        for (name, mesh) in meshes() {
+            for vertex in &mesh.vertices {
+                let length = dot(vertex.normal, vertex.normal).sqrt();
+                assert!((length - 1.0).abs() < 1e-5, "{name}: {vertex:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn triangles_face_their_normals() {
+        for (name, mesh) in meshes() {
+            for triangle in mesh.indices.as_chunks::<3>().0 {
+                let [a, b, c] = triangle.map(|index| &mesh.vertices[index as usize]);
+                let face = cross(
+                    [0, 1, 2].map(|axis| b.position[axis] - a.position[axis]),
+                    [0, 1, 2].map(|axis| c.position[axis] - a.position[axis]),
+                );
+                // the pole rows of spheres and the centers of caps collapse
+                if dot(face, face) < 1e-12 {
+                    continue;
+                }
+                for vertex in [a, b, c] {
+                    assert!(
+                        dot(face, vertex.normal) > 0.0,
+                        "{name}: triangle {triangle:?} winds against {vertex:?}"
+                    );
+                }
+            }
+        }
+    }
+}
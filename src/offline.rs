@@ -0,0 +1,283 @@
+use crate::config::{Configuration as _, DefaultConf as Conf};
+use crate::downsample::{Filter, downsample};
+use crate::error::SetupError;
+use crate::gpupipeline::{Renderer, Uniforms};
+use crate::model::Geometry;
+use crate::shader;
+use crate::tonemap::{self, Operator, Tonemap};
+use core::error::Error;
+use image::RgbaImage;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use tracing::info;
+use wgpu::{
+    AdapterInfo, Backends, BufferDescriptor, BufferUsages, COPY_BYTES_PER_ROW_ALIGNMENT,
+    CommandEncoderDescriptor, Device, Extent3d, Instance, InstanceDescriptor, MapMode, Origin3d,
+    PollType, Queue, TexelCopyBufferInfo, TexelCopyBufferLayout, TexelCopyTextureInfo, Texture,
+    TextureAspect, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+    TextureViewDescriptor,
+};
+
+/// Format of the offscreen targets read back. The tonemap pass writes linear
+/// colors and the sRGB view encodes them, same as the swapchain view in
+/// `State::render`.
+pub const TARGET_FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
+const BYTES_PER_PIXEL: u32 = 4;
+const DEFAULT_TILE_SIZE: u32 = 2048;
+
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub output: PathBuf,
+    pub shader: Option<PathBuf>,
+    pub width: u32,
+    pub height: u32,
+    pub time: f32,
+    /// Side of the square tiles the frame is split into. Clamped to the
+    /// device's max texture size.
+    pub tile_size: Option<u32>,
+    /// The frame is rendered at `ssaa` times the output size on each axis and
+    /// filtered down with `filter`.
+    pub ssaa: u32,
+    pub filter: Filter,
+    pub tonemap: Operator,
+    /// Stops, see [`Tonemap::exposure`].
+    pub exposure: f32,
+}
+
+/// A device with no window attached, for rendering straight to images.
+#[derive(Debug)]
+pub struct Headless {
+    pub adapter_info: AdapterInfo,
+    pub device: Device,
+    pub queue: Queue,
+}
+
+impl Headless {
+    /// Uses a hardware adapter when there is one and `software` isn't set,
+    /// otherwise the fallback software adapter.
+    ///
+    /// # Errors
+    /// When there is no adapter or it refuses to give a device.
+    pub async fn new(software: bool) -> Result<Headless, SetupError> {
+        let instance = Instance::new(&InstanceDescriptor {
+            backends: Backends::PRIMARY,
+            ..Default::default()
+        });
+
+        let hardware = if software {
+            None
+        } else {
+            instance
+                .request_adapter(&Conf::headless_adapter(false))
+                .await
+                .ok()
+        };
+        let adapter = match hardware {
+            Some(adapter) => adapter,
+            None => instance
+                .request_adapter(&Conf::headless_adapter(true))
+                .await
+                .map_err(SetupError::NoAdapter)?,
+        };
+        let (device, queue) = adapter
+            .request_device(&Conf::device_dec(
+                adapter.limits(),
+                adapter.features() & Conf::optional_features(),
+            ))
+            .await
+            .map_err(SetupError::NoDevice)?;
+
+        Ok(Headless {
+            adapter_info: adapter.get_info(),
+            device,
+            queue,
+        })
+    }
+}
+
+/// Renders the export described by `options` and writes it to `options.output`.
+///
+/// # Errors
+/// When there is no GPU to render with, the supersampled size doesn't fit in
+/// a `u32`, or the shader or image file can't be read or written.
+pub fn export(options: &ExportOptions) -> Result<(), Box<dyn Error>> {
+    let headless = pollster::block_on(Headless::new(false))?;
+    let shader = shader::Source::read(options.shader.as_deref())?;
+    let mut renderer = Renderer::new(&headless.device, &shader, tonemap::FORMAT, &Geometry::Quad)?;
+    let mut tonemap = Tonemap::new(&headless.device, TARGET_FORMAT, [1, 1]);
+    tonemap.operator = options.tonemap;
+    tonemap.exposure = options.exposure;
+
+    let ssaa = options.ssaa.max(1);
+    let (Some(width), Some(height)) = (
+        options.width.checked_mul(ssaa),
+        options.height.checked_mul(ssaa),
+    ) else {
+        return Err(format!(
+            "{}x{} with --ssaa {ssaa} is too large to render",
+            options.width, options.height
+        )
+        .into());
+    };
+    // every output pixel covers `ssaa` rendered ones, like a HiDPI screen
+    #[expect(clippy::cast_precision_loss, reason = "ssaa factors are small")]
+    let scale_factor = ssaa as f32;
+    let mut image = render_tiled(
+        &headless,
+        &mut renderer,
+        &mut tonemap,
+        [width, height],
+        options.time,
+        scale_factor,
+        options.tile_size.unwrap_or(DEFAULT_TILE_SIZE),
+    )?;
+    if ssaa > 1 {
+        image = downsample(&image, ssaa, options.filter);
+    }
+
+    image.save(&options.output)?;
+    info!("exported {}", options.output.display());
+    Ok(())
+}
+
+/// Renders a frame of `size` pixels in tiles of at most `tile_size` pixels a
+/// side and stitches them on the CPU. `scale_factor` is handed to the shader
+/// like a window's pixel density. `renderer` has to draw [`tonemap::FORMAT`],
+/// which `tonemap` maps to the [`TARGET_FORMAT`] tiles read back, as it does
+/// for windows.
+///
+/// Every tile is its own submission that is waited on before the next one,
+/// so a heavy shader never keeps the GPU busy long enough to trip a watchdog,
+/// and the final image may be larger than the device's max texture size.
+/// Compute passes run per tile too, over the pixels of the tile, and every
+/// tile starts from the same storage buffers, so they are stepped once for
+/// the whole frame.
+///
+/// # Errors
+/// When reading a tile back from the GPU fails.
+pub fn render_tiled(
+    headless: &Headless,
+    renderer: &mut Renderer,
+    tonemap: &mut Tonemap,
+    size: [u32; 2],
+    time: f32,
+    scale_factor: f32,
+    tile_size: u32,
+) -> Result<RgbaImage, Box<dyn Error>> {
+    let Headless { device, queue, .. } = headless;
+    let [width, height] = size;
+
+    let tile_size = tile_size
+        .min(device.limits().max_texture_dimension_2d)
+        .max(1);
+    let target_width = tile_size.min(width);
+    let target_height = tile_size.min(height);
+    renderer.resize(device, [target_width, target_height]);
+    tonemap.resize(device, [target_width, target_height]);
+
+    let target = target_texture(device, target_width, target_height);
+    let view = target.create_view(&TextureViewDescriptor::default());
+
+    let padded_bytes_per_row =
+        (target_width * BYTES_PER_PIXEL).next_multiple_of(COPY_BYTES_PER_ROW_ALIGNMENT);
+    let readback = device.create_buffer(&BufferDescriptor {
+        label: Some("offline_readback"),
+        size: u64::from(padded_bytes_per_row) * u64::from(target_height),
+        usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut pixels = vec![0; width as usize * height as usize * BYTES_PER_PIXEL as usize];
+    // every tile starts from the storage buffers the first one found
+    let tiled = target_width < width || target_height < height;
+    let mut snapshot: Option<Vec<_>> = None;
+    #[expect(clippy::cast_precision_loss, reason = "image sizes fit in f32")]
+    let resolution = [width as f32, height as f32];
+
+    for tile_y in (0..height).step_by(tile_size as usize) {
+        for tile_x in (0..width).step_by(tile_size as usize) {
+            let copy_width = target_width.min(width - tile_x);
+            let copy_height = target_height.min(height - tile_y);
+
+            #[expect(clippy::cast_precision_loss, reason = "image sizes fit in f32")]
+            let uniforms = Uniforms::new(resolution, time)
+                .with_scale_factor(scale_factor)
+                .with_tile_offset([tile_x as f32, tile_y as f32]);
+
+            let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("offline_tile"),
+            });
+            if tiled && let Some(storage) = renderer.storage.as_ref() {
+                match snapshot.as_deref() {
+                    Some(buffers) => storage.restore(&mut encoder, buffers),
+                    None => snapshot = Some(storage.snapshot(device, &mut encoder)),
+                }
+            }
+            renderer.draw(queue, &mut encoder, tonemap.target(), &uniforms, None);
+            tonemap.draw(queue, &mut encoder, &view, None);
+            encoder.copy_texture_to_buffer(
+                TexelCopyTextureInfo {
+                    texture: &target,
+                    mip_level: 0,
+                    origin: Origin3d::ZERO,
+                    aspect: TextureAspect::All,
+                },
+                TexelCopyBufferInfo {
+                    buffer: &readback,
+                    layout: TexelCopyBufferLayout {
+                        offset: 0,
+                        bytes_per_row: Some(padded_bytes_per_row),
+                        rows_per_image: Some(target_height),
+                    },
+                },
+                Extent3d {
+                    width: copy_width,
+                    height: copy_height,
+                    depth_or_array_layers: 1,
+                },
+            );
+            queue.submit([encoder.finish()]);
+
+            let slice = readback.slice(..);
+            let (sender, receiver) = mpsc::channel();
+            slice.map_async(MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+            device.poll(PollType::Wait)?;
+            receiver.recv()??;
+
+            {
+                let data = slice.get_mapped_range();
+                let row_len = (copy_width * BYTES_PER_PIXEL) as usize;
+                for row in 0..copy_height {
+                    let src = (row * padded_bytes_per_row) as usize;
+                    let dst = ((tile_y + row) as usize * width as usize + tile_x as usize)
+                        * BYTES_PER_PIXEL as usize;
+                    pixels[dst..dst + row_len].copy_from_slice(&data[src..src + row_len]);
+                }
+            }
+            readback.unmap();
+        }
+    }
+
+    Ok(RgbaImage::from_raw(width, height, pixels).ok_or("stitched image has the wrong size")?)
+}
+
+/// Offscreen color target the renderer can draw into and copy out of.
+#[must_use]
+pub fn target_texture(device: &Device, width: u32, height: u32) -> Texture {
+    device.create_texture(&TextureDescriptor {
+        label: Some("offline_target"),
+        size: Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format: TARGET_FORMAT,
+        usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+        view_formats: &[],
+    })
+}
@@ -0,0 +1,311 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use wgpu::{
+    Buffer, BufferAsyncError, BufferDescriptor, BufferUsages, CommandEncoder, Device, Features,
+    MapMode, PollType, QUERY_SIZE, QuerySet, QuerySetDescriptor, QueryType, Queue,
+    RenderPassTimestampWrites,
+};
+
+/// Most render passes that can be timed in a single frame.
+const MAX_PASSES: u32 = 8;
+/// How many frames the rolling statistics cover.
+const WINDOW: usize = 240;
+const REPORT_INTERVAL: Duration = Duration::from_secs(2);
+
+type Readback = Receiver<Result<(), BufferAsyncError>>;
+
+/// Rolling window of durations, in milliseconds.
+#[derive(Debug, Clone)]
+pub struct Samples {
+    values: VecDeque<f32>,
+    capacity: usize,
+}
+
+impl Samples {
+    /// Keeps the latest `capacity` samples.
+    #[must_use]
+    pub fn new(capacity: usize) -> Samples {
+        Samples {
+            values: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    pub fn push(&mut self, millis: f32) {
+        if self.values.len() == self.capacity {
+            self.values.pop_front();
+        }
+        self.values.push_back(millis);
+    }
+
+    #[must_use]
+    pub fn min(&self) -> Option<f32> {
+        self.values.iter().copied().min_by(f32::total_cmp)
+    }
+
+    #[must_use]
+    pub fn max(&self) -> Option<f32> {
+        self.values.iter().copied().max_by(f32::total_cmp)
+    }
+
+    #[must_use]
+    pub fn last(&self) -> Option<f32> {
+        self.values.back().copied()
+    }
+
+    #[must_use]
+    #[expect(
+        clippy::cast_precision_loss,
+        reason = "windows are far below 2^23 samples"
+    )]
+    pub fn mean(&self) -> Option<f32> {
+        (!self.values.is_empty())
+            .then(|| self.values.iter().sum::<f32>() / self.values.len() as f32)
+    }
+
+    /// Nearest-rank percentile, `percent` in `0.0..=100.0`.
+    #[must_use]
+    #[expect(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        reason = "the rank is clamped to the window"
+    )]
+    pub fn percentile(&self, percent: f32) -> Option<f32> {
+        let mut sorted: Vec<f32> = self.values.iter().copied().collect();
+        sorted.sort_by(f32::total_cmp);
+        let rank = (percent / 100.0 * sorted.len() as f32).ceil() as usize;
+        sorted.get(rank.clamp(1, sorted.len().max(1)) - 1).copied()
+    }
+
+    fn summary(&self) -> String {
+        format!(
+            "mean {:.2} ms, p50 {:.2} ms, p95 {:.2} ms, p99 {:.2} ms",
+            self.mean().unwrap_or_default(),
+            self.percentile(50.0).unwrap_or_default(),
+            self.percentile(95.0).unwrap_or_default(),
+            self.percentile(99.0).unwrap_or_default(),
+        )
+    }
+}
+
+impl Default for Samples {
+    fn default() -> Samples {
+        Samples::new(WINDOW)
+    }
+}
+
+/// Frame timing for the window: GPU time of every render pass through
+/// timestamp queries when the device has `TIMESTAMP_QUERY`, and CPU time
+/// between frames always.
+///
+/// Per frame: `begin_frame`, one `timestamp_writes` per pass, `resolve` before
+/// finishing the encoder and `submitted` right after the submit.
+#[derive(Debug)]
+pub struct Profiler {
+    /// Time between the starts of consecutive frames.
+    pub cpu_frame: Samples,
+    /// Time from the start of a frame to its submission.
+    pub cpu_work: Samples,
+    pub gpu_passes: BTreeMap<&'static str, Samples>,
+    frame_start: Option<Instant>,
+    gpu: Option<GpuTimer>,
+    last_report: Instant,
+}
+
+#[derive(Debug)]
+struct GpuTimer {
+    query_set: QuerySet,
+    resolve_buffer: Buffer,
+    readback_buffer: Buffer,
+    /// Nanoseconds per timestamp tick.
+    period: f32,
+    /// Passes timed in the frame being recorded.
+    recording: Vec<&'static str>,
+    /// Passes whose timestamps are being copied back to the CPU.
+    in_flight: Option<(Vec<&'static str>, Readback)>,
+}
+
+impl Profiler {
+    #[must_use]
+    pub fn new(device: &Device, queue: &Queue) -> Profiler {
+        let gpu = if device.features().contains(Features::TIMESTAMP_QUERY) {
+            Some(GpuTimer::new(device, queue))
+        } else {
+            info!("TIMESTAMP_QUERY is not supported, profiling CPU frame time only");
+            None
+        };
+
+        Profiler {
+            cpu_frame: Samples::default(),
+            cpu_work: Samples::default(),
+            gpu_passes: BTreeMap::new(),
+            frame_start: None,
+            gpu,
+            last_report: Instant::now(),
+        }
+    }
+
+    #[must_use]
+    pub fn has_gpu_timing(&self) -> bool {
+        self.gpu.is_some()
+    }
+
+    /// Frames per second over the rolling window.
+    #[must_use]
+    pub fn fps(&self) -> Option<f32> {
+        self.cpu_frame.mean().map(|millis| 1000.0 / millis)
+    }
+
+    /// Sum of the latest GPU time of every pass, in milliseconds.
+    #[must_use]
+    pub fn gpu_frame(&self) -> Option<f32> {
+        self.has_gpu_timing()
+            .then(|| self.gpu_passes.values().filter_map(Samples::last).sum())
+    }
+
+    pub fn begin_frame(&mut self, device: &Device) {
+        let now = Instant::now();
+        if let Some(start) = self.frame_start.replace(now) {
+            self.cpu_frame.push((now - start).as_secs_f32() * 1000.0);
+        }
+
+        if let Some(gpu) = &mut self.gpu {
+            let _ = device.poll(PollType::Poll);
+            gpu.collect(&mut self.gpu_passes);
+        }
+
+        if now - self.last_report >= REPORT_INTERVAL {
+            self.last_report = now;
+            self.report();
+        }
+    }
+
+    /// Timestamp writes for a render pass named `pass`, or `None` when GPU
+    /// timing is unavailable or the previous results are still being read.
+    pub fn timestamp_writes(
+        &mut self,
+        pass: &'static str,
+    ) -> Option<RenderPassTimestampWrites<'_>> {
+        let gpu = self.gpu.as_mut()?;
+        if gpu.in_flight.is_some() {
+            return None;
+        }
+
+        let index = u32::try_from(gpu.recording.len()).ok()?;
+        if index >= MAX_PASSES {
+            warn!("only {MAX_PASSES} passes can be timed, skipping `{pass}`");
+            return None;
+        }
+        gpu.recording.push(pass);
+
+        Some(RenderPassTimestampWrites {
+            query_set: &gpu.query_set,
+            beginning_of_pass_write_index: Some(index * 2),
+            end_of_pass_write_index: Some(index * 2 + 1),
+        })
+    }
+
+    /// Records the copy of this frame's timestamps into the readback buffer.
+    pub fn resolve(&self, encoder: &mut CommandEncoder) {
+        let Some(gpu) = &self.gpu else { return };
+        if gpu.recording.is_empty() {
+            return;
+        }
+
+        let queries = u32::try_from(gpu.recording.len() * 2).unwrap_or(MAX_PASSES * 2);
+        encoder.resolve_query_set(&gpu.query_set, 0..queries, &gpu.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &gpu.resolve_buffer,
+            0,
+            &gpu.readback_buffer,
+            0,
+            u64::from(queries * QUERY_SIZE),
+        );
+    }
+
+    /// Starts reading back the timestamps recorded this frame.
+    pub fn submitted(&mut self) {
+        if let Some(start) = self.frame_start {
+            self.cpu_work.push(start.elapsed().as_secs_f32() * 1000.0);
+        }
+
+        let Some(gpu) = &mut self.gpu else { return };
+        if gpu.recording.is_empty() {
+            return;
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        gpu.readback_buffer
+            .slice(..)
+            .map_async(MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+        gpu.in_flight = Some((std::mem::take(&mut gpu.recording), receiver));
+    }
+
+    fn report(&self) {
+        if self.cpu_frame.last().is_some() {
+            info!("cpu frame: {}", self.cpu_frame.summary());
+            info!("cpu work: {}", self.cpu_work.summary());
+        }
+        for (pass, samples) in &self.gpu_passes {
+            info!("gpu {pass}: {}", samples.summary());
+        }
+    }
+}
+
+impl GpuTimer {
+    fn new(device: &Device, queue: &Queue) -> GpuTimer {
+        let size = u64::from(MAX_PASSES * 2 * QUERY_SIZE);
+        GpuTimer {
+            query_set: device.create_query_set(&QuerySetDescriptor {
+                label: Some("profiler_queries"),
+                ty: QueryType::Timestamp,
+                count: MAX_PASSES * 2,
+            }),
+            resolve_buffer: device.create_buffer(&BufferDescriptor {
+                label: Some("profiler_resolve"),
+                size,
+                usage: BufferUsages::QUERY_RESOLVE | BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            readback_buffer: device.create_buffer(&BufferDescriptor {
+                label: Some("profiler_readback"),
+                size,
+                usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }),
+            period: queue.get_timestamp_period(),
+            recording: Vec::new(),
+            in_flight: None,
+        }
+    }
+
+    /// Moves finished readbacks into `passes`, if there are any.
+    #[expect(clippy::cast_precision_loss, reason = "pass durations are small")]
+    fn collect(&mut self, passes: &mut BTreeMap<&'static str, Samples>) {
+        let Some((names, receiver)) = &self.in_flight else {
+            return;
+        };
+
+        match receiver.try_recv() {
+            Err(TryRecvError::Empty) => return,
+            Ok(Ok(())) => {
+                let data = self.readback_buffer.slice(..).get_mapped_range();
+                let ticks: &[u64] = bytemuck::cast_slice(&data);
+                for (name, [start, end]) in names.iter().zip(ticks.as_chunks::<2>().0) {
+                    let nanos = end.saturating_sub(*start) as f32 * self.period;
+                    passes.entry(name).or_default().push(nanos / 1_000_000.0);
+                }
+                drop(data);
+                self.readback_buffer.unmap();
+            }
+            Ok(Err(error)) => warn!("reading GPU timestamps failed: {error}"),
+            Err(TryRecvError::Disconnected) => warn!("GPU timestamp readback was dropped"),
+        }
+        self.in_flight = None;
+    }
+}
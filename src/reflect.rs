@@ -0,0 +1,290 @@
+use crate::error::SetupError;
+use crate::{compute, storage};
+use naga::valid::ModuleInfo;
+use naga::{AddressSpace, ImageClass, ShaderStage, TypeInner};
+use std::collections::BTreeMap;
+use wgpu::ShaderStages;
+
+/// Group of the uniforms.
+pub const UNIFORMS_GROUP: u32 = 0;
+
+/// What the app binds for a shader, each kind in its own group. There are no
+/// input textures, samplers or keyboard state to bind yet, so shaders can't
+/// declare those.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
+    /// [`crate::gpupipeline::Uniforms`], at any binding of
+    /// [`UNIFORMS_GROUP`].
+    Uniforms,
+    /// The texture [`compute::ENTRY_POINT`] writes, at
+    /// `@group(1) @binding(0)`, read as a `texture_2d<f32>` by the other
+    /// stages.
+    ComputeOutput,
+    /// A buffer at [`storage::GROUP`], see [`storage::declarations`].
+    StorageBuffer,
+}
+
+/// A resource declared by a shader, and the stages of its entry points that
+/// use it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Binding {
+    pub resource: Resource,
+    pub group: u32,
+    pub binding: u32,
+    pub visibility: ShaderStages,
+}
+
+/// The resources `module` declares, one per binding, whatever their
+/// variables are called. `info` is the validation result of `module`.
+/// `drawn_for_compute` is set for a module drawing the output of another
+/// one's compute pass, which otherwise only exists when `module` has a
+/// [`compute::ENTRY_POINT`] itself.
+///
+/// # Errors
+/// [`SetupError::Binding`] for a binding that isn't one of the
+/// [`Resource`]s, which nothing would be bound to.
+pub fn bindings(
+    name: &str,
+    module: &naga::Module,
+    info: &ModuleInfo,
+    drawn_for_compute: bool,
+) -> Result<Vec<Binding>, SetupError> {
+    let computes = drawn_for_compute
+        || module.entry_points.iter().any(|entry_point| {
+            entry_point.stage == ShaderStage::Compute && entry_point.name == compute::ENTRY_POINT
+        });
+    let mut bindings = BTreeMap::new();
+    for (handle, variable) in module.global_variables.iter() {
+        let Some(bound) = variable.binding.as_ref() else {
+            continue;
+        };
+        let image = matches!(
+            module.types[variable.ty].inner,
+            TypeInner::Image {
+                class: ImageClass::Sampled { .. } | ImageClass::Storage { .. },
+                ..
+            }
+        );
+        let resource = match (bound.group, bound.binding, variable.space) {
+            (UNIFORMS_GROUP, _, AddressSpace::Uniform) => Resource::Uniforms,
+            (compute::GROUP, 0, AddressSpace::Handle) if image && computes => {
+                Resource::ComputeOutput
+            }
+            (storage::GROUP, _, AddressSpace::Storage { .. }) => Resource::StorageBuffer,
+            (compute::GROUP, 0, AddressSpace::Handle) if image => {
+                return Err(SetupError::Binding {
+                    name: name.to_owned(),
+                    reason: format!(
+                        "@group({}) @binding(0) {} is the output of {}, which the shader doesn't have",
+                        compute::GROUP,
+                        variable.name.as_deref().unwrap_or(""),
+                        compute::ENTRY_POINT
+                    ),
+                });
+            }
+            _ => {
+                return Err(SetupError::Binding {
+                    name: name.to_owned(),
+                    reason: format!(
+                        "nothing is bound to @group({}) @binding({}) {}",
+                        bound.group,
+                        bound.binding,
+                        variable.name.as_deref().unwrap_or("")
+                    ),
+                });
+            }
+        };
+
+        let visibility = module
+            .entry_points
+            .iter()
+            .enumerate()
+            .filter(|&(index, _)| !info.get_entry_point(index)[handle].is_empty())
+            .fold(ShaderStages::NONE, |visibility, (_, entry_point)| {
+                visibility | stage(entry_point.stage)
+            });
+        bindings
+            .entry((bound.group, bound.binding))
+            .and_modify(|binding: &mut Binding| binding.visibility |= visibility)
+            .or_insert(Binding {
+                resource,
+                group: bound.group,
+                binding: bound.binding,
+                visibility,
+            });
+    }
+    Ok(bindings.into_values().collect())
+}
+
+/// The stages each binding of `resource` is used from, merged across the
+/// shaders `bindings` come from, which share one pipeline layout.
+pub fn visibility<'a>(
+    bindings: impl IntoIterator<Item = &'a Binding>,
+    resource: Resource,
+) -> BTreeMap<u32, ShaderStages> {
+    let mut visibility = BTreeMap::new();
+    for binding in bindings {
+        if binding.resource == resource {
+            *visibility
+                .entry(binding.binding)
+                .or_insert(ShaderStages::NONE) |= binding.visibility;
+        }
+    }
+    visibility
+}
+
+/// The wgpu flag of `stage`.
+fn stage(stage: ShaderStage) -> ShaderStages {
+    match stage {
+        ShaderStage::Vertex => ShaderStages::VERTEX,
+        ShaderStage::Fragment => ShaderStages::FRAGMENT,
+        ShaderStage::Compute => ShaderStages::COMPUTE,
+        ShaderStage::Task => ShaderStages::TASK,
+        ShaderStage::Mesh => ShaderStages::MESH,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use naga::valid::{Capabilities, ValidationFlags, Validator};
+
+    fn reflect(code: &str, drawn_for_compute: bool) -> Result<Vec<Binding>, String> {
+        let module = naga::front::wgsl::parse_str(code).expect("the snippet parses");
+        let info = Validator::new(ValidationFlags::all(), Capabilities::all())
+            .validate(&module)
+            .expect("the snippet validates");
+        bindings("probe.wgsl", &module, &info, drawn_for_compute).map_err(|error| error.to_string())
+    }
+
+    #[test]
+    fn uniforms_are_any_binding_of_group_0() {
+        let code = "
+            @group(0) @binding(2) var<uniform> settings: vec4<f32>;
+            @fragment
+            fn fs_main() -> @location(0) vec4<f32> {
+                return settings;
+            }
+        ";
+        assert_eq!(
+            reflect(code, false),
+            Ok(vec![Binding {
+                resource: Resource::Uniforms,
+                group: 0,
+                binding: 2,
+                visibility: ShaderStages::FRAGMENT,
+            }])
+        );
+    }
+
+    #[test]
+    fn unknown_bindings_are_rejected() {
+        let code = "
+            @group(3) @binding(0) var<uniform> extra: vec4<f32>;
+            @fragment
+            fn fs_main() -> @location(0) vec4<f32> {
+                return extra;
+            }
+        ";
+        let error = reflect(code, false).unwrap_err();
+        assert!(
+            error.contains("nothing is bound to @group(3) @binding(0) extra"),
+            "{error}"
+        );
+
+        let sampler = "
+            @group(1) @binding(1) var linear: sampler;
+            @fragment
+            fn fs_main() -> @location(0) vec4<f32> {
+                return vec4<f32>(1.0);
+            }
+        ";
+        let error = reflect(sampler, false).unwrap_err();
+        assert!(
+            error.contains("nothing is bound to @group(1) @binding(1) linear"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn compute_output_needs_cs_main() {
+        let code = "
+            @group(1) @binding(0) var output: texture_2d<f32>;
+            @fragment
+            fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+                return textureLoad(output, vec2<i32>(position.xy), 0);
+            }
+        ";
+        let error = reflect(code, false).unwrap_err();
+        assert!(
+            error.contains("output is the output of cs_main, which the shader doesn't have"),
+            "{error}"
+        );
+
+        let drawn = reflect(code, true).expect("the blit of another shader's output");
+        assert_eq!(drawn[0].resource, Resource::ComputeOutput);
+
+        let computed = "
+            @group(1) @binding(0) var output: texture_storage_2d<rgba16float, write>;
+            @compute @workgroup_size(8, 8)
+            fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+                textureStore(output, id.xy, vec4<f32>(1.0));
+            }
+        ";
+        assert_eq!(
+            reflect(computed, false),
+            Ok(vec![Binding {
+                resource: Resource::ComputeOutput,
+                group: 1,
+                binding: 0,
+                visibility: ShaderStages::COMPUTE,
+            }])
+        );
+    }
+
+    #[test]
+    fn visibility_is_every_stage_using_a_binding() {
+        let code = "
+            @group(0) @binding(0) var<uniform> scale: f32;
+            @group(0) @binding(1) var<uniform> unused: f32;
+            @group(2) @binding(0) var<storage, read_write> cells: array<f32, 4>;
+            @group(2) @binding(0) var<storage, read> cells_in: array<f32, 4>;
+
+            @vertex
+            fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+                return vec4<f32>(f32(index) * scale, 0.0, 0.0, 1.0);
+            }
+
+            @fragment
+            fn fs_main() -> @location(0) vec4<f32> {
+                return vec4<f32>(scale * cells_in[0]);
+            }
+
+            @compute @workgroup_size(1)
+            fn cs_main() {
+                cells[0] += 1.0;
+            }
+        ";
+        let visibility: Vec<_> = reflect(code, false)
+            .expect("every binding is known")
+            .into_iter()
+            .map(|binding| (binding.resource, binding.binding, binding.visibility))
+            .collect();
+        assert_eq!(
+            visibility,
+            [
+                (
+                    Resource::Uniforms,
+                    0,
+                    ShaderStages::VERTEX | ShaderStages::FRAGMENT
+                ),
+                (Resource::Uniforms, 1, ShaderStages::NONE),
+                (
+                    Resource::StorageBuffer,
+                    0,
+                    ShaderStages::COMPUTE | ShaderStages::FRAGMENT
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,306 @@
+use crate::error::SetupError;
+use crate::gpupipeline::Uniforms;
+use crate::layout::HostStruct as _;
+use crate::model::Geometry;
+use crate::reflect::{self, Resource};
+use crate::{layout, storage};
+use naga::valid::{Capabilities, ModuleInfo, ValidationFlags, Validator};
+use std::fs;
+use std::path::Path;
+use wgpu::{Device, ShaderModule, ShaderModuleDescriptor, ShaderSource};
+
+/// The example shader used when no file is given.
+const BUNDLED: &str = include_str!("shader.wgsl");
+
+/// The shader used for models when no file is given.
+const LIT: &str = include_str!("lit.wgsl");
+
+/// The shader used for bare vertices when no file is given.
+const VERTEX_ART: &str = include_str!("vertex_art.wgsl");
+
+/// Shows the output of a shader that only has a compute entry point.
+const COMPUTE_BLIT: &str = include_str!("compute_blit.wgsl");
+
+/// Where the bundled shaders are, to find their storage buffer contents in
+/// the checkout the binary was built from.
+const BUNDLED_DIRECTORY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src");
+
+/// WGSL that is known to compile, kept around so it can be compiled again
+/// on a new device.
+#[derive(Debug, Clone)]
+pub struct Source {
+    /// The file it was read from, or `shader.wgsl` for the bundled one.
+    pub name: String,
+    pub code: String,
+    /// What naga made of `code`, for looking at entry points and bindings.
+    pub module: naga::Module,
+    /// The storage buffers it declares, with their initial contents.
+    pub buffers: Vec<storage::Declaration>,
+    /// Every resource it declares, with the stages that use it.
+    pub bindings: Vec<reflect::Binding>,
+}
+
+impl Source {
+    /// Parses and validates `code`, with `name` used as the file name in the
+    /// diagnostics.
+    ///
+    /// Initial contents of storage buffers are read from the directory of
+    /// `name`, the working directory for a bare file name. Unless `code`
+    /// declares its own `struct Uniforms`, the one generated from
+    /// [`Uniforms`] is appended to it.
+    ///
+    /// # Errors
+    /// [`SetupError::CompileShader`] with the compiler diagnostics,
+    /// [`SetupError::Binding`] for a resource nothing is bound to,
+    /// [`SetupError::UniformLayout`] when its uniforms aren't laid out like
+    /// [`Uniforms`], or when the storage buffers can't be set up.
+    pub fn new(name: String, code: String) -> Result<Source, SetupError> {
+        let directory = Path::new(&name).parent().map(Path::to_path_buf);
+        Source::in_directory(name, code, directory.as_deref(), false)
+    }
+
+    /// [`Source::new`] with the storage buffer contents read from
+    /// `directory`. `drawn_for_compute` is for a shader drawing the output
+    /// of another one's compute pass, see [`reflect::bindings`].
+    fn in_directory(
+        name: String,
+        code: String,
+        directory: Option<&Path>,
+        drawn_for_compute: bool,
+    ) -> Result<Source, SetupError> {
+        let code = with_uniforms(code);
+        let (module, info) = validate(&name, &code)?;
+        let bindings = reflect::bindings(&name, &module, &info, drawn_for_compute)?;
+        for binding in reflect::visibility(&bindings, Resource::Uniforms).into_keys() {
+            layout::check::<Uniforms>(&name, &module, reflect::UNIFORMS_GROUP, binding)?;
+        }
+        let buffers = storage::declarations(&module, directory)?;
+        Ok(Source {
+            name,
+            code,
+            module,
+            buffers,
+            bindings,
+        })
+    }
+
+    /// # Panics
+    /// If the bundled shader doesn't compile, which the golden tests catch.
+    #[must_use]
+    pub fn bundled() -> Source {
+        Source::bundled_file("shader.wgsl", BUNDLED, false).expect("the bundled shader compiles")
+    }
+
+    /// Plain shading for models, drawn with the camera and model matrices.
+    ///
+    /// # Panics
+    /// If the bundled shader doesn't compile.
+    #[must_use]
+    pub fn lit() -> Source {
+        Source::bundled_file("lit.wgsl", LIT, false).expect("the lit shader compiles")
+    }
+
+    /// The shader `geometry` is drawn with when no file is given: the
+    /// bundled ray marcher on the quad, [`Source::lit`] on models and
+    /// [`Source::vertex_art`] on bare vertices.
+    #[must_use]
+    pub fn default_for(geometry: &Geometry) -> Source {
+        match geometry {
+            Geometry::Quad => Source::bundled(),
+            Geometry::Mesh(_) => Source::lit(),
+            Geometry::Vertices { .. } => Source::vertex_art(),
+        }
+    }
+
+    /// Places bare vertices from their index, see
+    /// [`crate::model::Geometry::Vertices`].
+    ///
+    /// # Panics
+    /// If the bundled shader doesn't compile.
+    #[must_use]
+    pub fn vertex_art() -> Source {
+        Source::bundled_file("vertex_art.wgsl", VERTEX_ART, false)
+            .expect("the vertex art shader compiles")
+    }
+
+    /// Draws the compute output of a shader that has no `fs_main`.
+    ///
+    /// # Panics
+    /// If the bundled shader doesn't compile.
+    #[must_use]
+    pub fn compute_blit() -> Source {
+        Source::bundled_file("compute_blit.wgsl", COMPUTE_BLIT, true)
+            .expect("the compute blit shader compiles")
+    }
+
+    /// One of the shaders built into the binary, `file` in
+    /// [`BUNDLED_DIRECTORY`].
+    fn bundled_file(file: &str, code: &str, drawn_for_compute: bool) -> Result<Source, SetupError> {
+        Source::in_directory(
+            file.to_owned(),
+            code.to_owned(),
+            Some(Path::new(BUNDLED_DIRECTORY)),
+            drawn_for_compute,
+        )
+    }
+
+    /// Reads and validates the WGSL file at `path`, or the bundled example
+    /// shader when no path is given.
+    ///
+    /// # Errors
+    /// When the file can't be read or doesn't compile.
+    pub fn read(path: Option<&Path>) -> Result<Source, SetupError> {
+        let Some(file) = path else {
+            return Ok(Source::bundled());
+        };
+
+        let code = fs::read_to_string(file).map_err(|source| SetupError::ReadShader {
+            path: file.to_path_buf(),
+            source,
+        })?;
+        Source::new(file.display().to_string(), code)
+    }
+
+    #[must_use]
+    pub fn compile(&self, device: &Device) -> ShaderModule {
+        device.create_shader_module(ShaderModuleDescriptor {
+            label: Some(&self.name),
+            source: ShaderSource::Wgsl(self.code.as_str().into()),
+        })
+    }
+
+    /// The entry point called `name`, of any stage.
+    #[must_use]
+    pub fn entry_point(&self, name: &str) -> Option<&naga::EntryPoint> {
+        self.module
+            .entry_points
+            .iter()
+            .find(|entry_point| entry_point.name == name)
+    }
+
+    /// The file name without its directories, for display.
+    #[must_use]
+    pub fn file_name(&self) -> String {
+        Path::new(&self.name)
+            .file_name()
+            .map_or_else(|| self.name.clone(), |name| name.to_string_lossy().into())
+    }
+}
+
+/// `code` with the WGSL declaration of [`Uniforms`] appended, unless it
+/// declares a struct of that name itself. Appending keeps the line numbers in
+/// diagnostics those of the file.
+fn with_uniforms(mut code: String) -> String {
+    let declared = without_comments(&code)
+        .split(|character: char| !(character.is_alphanumeric() || character == '_'))
+        .filter(|token| !token.is_empty())
+        .collect::<Vec<_>>()
+        .windows(2)
+        .any(|tokens| tokens == ["struct", Uniforms::NAME]);
+    if !declared {
+        code.push_str("\n// Generated from the host type, see `Uniforms` in src/gpupipeline.rs.\n");
+        code.push_str(&layout::wgsl::<Uniforms>());
+    }
+    code
+}
+
+/// `code` with its line and block comments, which may nest, replaced by
+/// spaces.
+fn without_comments(code: &str) -> String {
+    let mut stripped = String::with_capacity(code.len());
+    let mut depth = 0_u32;
+    let mut rest = code;
+    while let Some(character) = rest.chars().next() {
+        if rest.starts_with("/*") {
+            depth += 1;
+            rest = &rest[2..];
+        } else if depth > 0 && rest.starts_with("*/") {
+            depth -= 1;
+            rest = &rest[2..];
+        } else if depth == 0 && rest.starts_with("//") {
+            rest = rest.find('\n').map_or("", |end| &rest[end..]);
+        } else {
+            stripped.push(if depth == 0 { character } else { ' ' });
+            rest = &rest[character.len_utf8()..];
+            continue;
+        }
+        stripped.push(' ');
+    }
+    stripped
+}
+
+/// Parses and validates `source`, with `name` used as the file name in the
+/// diagnostics. Returns the module along with what validation found out
+/// about it.
+///
+/// The source is checked with naga before it reaches wgpu, which would only
+/// report a failed compile through its uncaptured error handler.
+fn validate(name: &str, source: &str) -> Result<(naga::Module, ModuleInfo), SetupError> {
+    let compile_error = |diagnostics| SetupError::CompileShader {
+        name: name.to_owned(),
+        diagnostics,
+    };
+
+    let module = naga::front::wgsl::parse_str(source)
+        .map_err(|error| compile_error(error.emit_to_string_with_path(source, name)))?;
+
+    // Device specific capabilities are left to wgpu, this only catches
+    // mistakes in the source.
+    let info = Validator::new(ValidationFlags::all(), Capabilities::all())
+        .validate(&module)
+        .map_err(|error| compile_error(error.emit_to_string_with_path(source, name)))?;
+
+    Ok((module, info))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{self, Topology};
+    use crate::vertex;
+
+    #[test]
+    fn comments_dont_declare_uniforms() {
+        let code = "// struct Uniforms is generated\n/* struct Uniforms */\n".to_owned();
+        assert!(with_uniforms(code).contains(&layout::wgsl::<Uniforms>()));
+
+        let nested = "/* /* */ struct Uniforms */".to_owned();
+        assert!(with_uniforms(nested).contains(&layout::wgsl::<Uniforms>()));
+    }
+
+    #[test]
+    fn declared_uniforms_are_kept() {
+        let code = "struct Uniforms { time: f32 } // mine\n".to_owned();
+        assert_eq!(with_uniforms(code.clone()), code);
+    }
+
+    #[test]
+    fn comments_are_blanked_out() {
+        assert_eq!(without_comments("a // b\nc"), "a  \nc");
+        let nested = without_comments("a /* b /* c */ d */ e");
+        assert_eq!(nested.split_whitespace().collect::<Vec<_>>(), ["a", "e"]);
+    }
+
+    #[test]
+    fn defaults_can_draw_their_geometry() {
+        let vertices = Geometry::Vertices {
+            count: 3,
+            topology: Topology::default(),
+        };
+        for geometry in [
+            Geometry::Quad,
+            Geometry::Mesh(model::cube(1.0)),
+            vertices.clone(),
+        ] {
+            let source = Source::default_for(&geometry);
+            let layout = geometry.vertex_layout();
+            if let Err(error) = vertex::check(&source.name, &source.module, layout.as_ref()) {
+                panic!("{error}");
+            }
+        }
+
+        // so it can't stand in as the known-good shader of bare vertices
+        let bundled = Source::bundled();
+        assert!(vertex::check(&bundled.name, &bundled.module, None).is_err());
+    }
+}
@@ -31,11 +31,11 @@ alias Direction = vec3<f32>;
 alias Dimensions = vec3<f32>;
 const PHI: f32 = 1.6180339887498948482046;
 
+// `Uniforms` is declared by the app, with the fields of `Uniforms` in
+// src/gpupipeline.rs: `resolution`, `time`, `scale_factor`, `tile_offset`,
+// `vertex_count`, and the `model`, `view` and `projection` matrices that are
+// only set when drawing a model.
 @group(0) @binding(0) var<uniform> uniforms: Uniforms;
-struct Uniforms {
-    resolution: vec2<f32>,
-    time: f32,
-}
 
 struct Material {
     color: Color,
@@ -159,7 +159,8 @@ fn diffuse_light_at(point: vec3<f32>) -> f32 {
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let uv = (in.clip_position.xy - (0.5 * uniforms.resolution)) / uniforms.resolution.y;
+    let frag_coord = in.clip_position.xy + uniforms.tile_offset;
+    let uv = (frag_coord - (0.5 * uniforms.resolution)) / uniforms.resolution.y;
     let camera_position = vec3<f32>(0.0, 0.0, 5.0);
     let ray_direction = normalize(vec3<f32>(uv.x, -uv.y, -1.0));
 
@@ -175,6 +176,39 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     return fragColor;
 }
 
+struct RaymarchOutput {
+    @location(0) color: vec4<f32>,
+    @builtin(frag_depth) depth: f32,
+}
+
+// The same scene seen through the camera models are drawn with, for mixing
+// the two with `--model`.
+@fragment
+fn fs_raymarch(@builtin(position) position: vec4<f32>) -> RaymarchOutput {
+    let frag_coord = position.xy + uniforms.tile_offset;
+    let ndc = vec2<f32>(frag_coord.x, uniforms.resolution.y - frag_coord.y) / uniforms.resolution * 2.0 - 1.0;
+
+    // look_at views are a rotation and a translation, so the transposed
+    // rotation undoes them without a general inverse
+    let rotation = transpose(mat3x3<f32>(uniforms.view[0].xyz, uniforms.view[1].xyz, uniforms.view[2].xyz));
+    let camera_position = -(rotation * uniforms.view[3].xyz);
+    let ray_direction = normalize(rotation * vec3<f32>(
+        ndc.x / uniforms.projection[0][0],
+        ndc.y / uniforms.projection[1][1],
+        -1.0,
+    ));
+
+    let distance = ray_march(camera_position, ray_direction);
+    if distance > MAX_VIEW_DEPTH { discard; }
+    let point = camera_position + ray_direction * distance;
+
+    let clip = uniforms.projection * uniforms.view * vec4<f32>(point, 1.0);
+    var out: RaymarchOutput;
+    out.color = vec4<f32>(vec3(diffuse_light_at(point)), 1.0);
+    out.depth = clip.z / clip.w;
+    return out;
+}
+
 
 // ----------------------------------------------------------------------
 // ----------------------------------------------------------------------
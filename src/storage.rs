@@ -0,0 +1,335 @@
+use crate::error::SetupError;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use wgpu::util::DeviceExt as _;
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingType, Buffer, BufferBindingType, BufferDescriptor, BufferUsages,
+    CommandEncoder, Device, ShaderStages,
+};
+
+/// Bind group of the storage buffers, in both compute and fragment stages.
+pub const GROUP: u32 = 2;
+
+/// A storage buffer a shader declares at [`GROUP`], as in
+/// `@group(2) @binding(0) var<storage, read_write> particles: array<Particle, 4096>;`
+#[derive(Debug, Clone)]
+pub struct Declaration {
+    /// Name of the variable, preferring the `read_write` one when a binding
+    /// is declared twice.
+    pub name: String,
+    pub binding: u32,
+    pub size: u64,
+    /// Initial contents from `<name>.bin` next to the shader, zeros otherwise.
+    pub contents: Option<Vec<u8>>,
+}
+
+/// The storage buffers declared by `module`. Their sizes come from the WGSL
+/// types, or from the contents file for runtime sized arrays.
+///
+/// # Errors
+/// When a contents file can't be read, is larger than its buffer, or a
+/// runtime sized buffer has no contents file to take its size from.
+pub fn declarations(
+    module: &naga::Module,
+    directory: Option<&Path>,
+) -> Result<Vec<Declaration>, SetupError> {
+    let mut variables = BTreeMap::new();
+    for (_, variable) in module.global_variables.iter() {
+        let (Some(binding), naga::AddressSpace::Storage { access }) =
+            (variable.binding.as_ref(), variable.space)
+        else {
+            continue;
+        };
+        if binding.group != GROUP {
+            continue;
+        }
+        let writable = access.contains(naga::StorageAccess::STORE);
+        let entry = variables
+            .entry(binding.binding)
+            .or_insert((variable, writable));
+        if writable && !entry.1 {
+            *entry = (variable, writable);
+        }
+    }
+
+    variables
+        .into_iter()
+        .map(|(binding, (variable, _))| {
+            let name = variable
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("binding{binding}"));
+            let inner = &module.types[variable.ty].inner;
+
+            let contents = match directory.map(|directory| directory.join(format!("{name}.bin"))) {
+                Some(path) if path.exists() => {
+                    Some(fs::read(&path).map_err(|source| SetupError::ReadBuffer {
+                        path: path.clone(),
+                        source,
+                    })?)
+                }
+                _ => None,
+            };
+
+            let declared = u64::from(inner.size(module.to_ctx()));
+            let size = match contents.as_ref() {
+                Some(bytes) if inner.is_dynamically_sized(&module.types) => {
+                    (bytes.len() as u64).next_multiple_of(4).max(declared)
+                }
+                Some(bytes) if bytes.len() as u64 > declared => {
+                    return Err(SetupError::BufferSize {
+                        reason: format!("{name}.bin has {} bytes, over {declared}", bytes.len()),
+                        name,
+                    });
+                }
+                None if inner.is_dynamically_sized(&module.types) => {
+                    return Err(SetupError::BufferSize {
+                        name,
+                        reason: "a runtime sized array needs a .bin file to take its size from"
+                            .into(),
+                    });
+                }
+                _ => declared,
+            };
+
+            Ok(Declaration {
+                name,
+                binding,
+                size,
+                contents,
+            })
+        })
+        .collect()
+}
+
+/// Buffers that outlive frames, bound read-write to the compute pass and
+/// read-only to the render stages.
+#[derive(Debug)]
+pub struct Storage {
+    pub buffers: Vec<Buffer>,
+    pub compute_bind_group: BindGroup,
+    pub compute_layout: BindGroupLayout,
+    /// Stands in for group 1 when there is no compute output there.
+    pub empty_bind_group: BindGroup,
+    pub empty_layout: BindGroupLayout,
+    pub render_bind_group: BindGroup,
+    pub render_layout: BindGroupLayout,
+}
+
+impl Storage {
+    /// `visibility` has the stages using each binding, of which the render
+    /// stages get it read-only. `None` when there is nothing declared.
+    #[must_use]
+    pub fn new(
+        device: &Device,
+        declarations: &[Declaration],
+        visibility: &BTreeMap<u32, ShaderStages>,
+    ) -> Option<Storage> {
+        if declarations.is_empty() {
+            return None;
+        }
+
+        let buffers: Vec<Buffer> = declarations
+            .iter()
+            .map(|declaration| {
+                let mut contents = declaration.contents.clone().unwrap_or_default();
+                contents.resize(usize::try_from(declaration.size).unwrap_or(0), 0);
+                device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some(&declaration.name),
+                    contents: &contents,
+                    usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+                })
+            })
+            .collect();
+
+        let (compute_layout, compute_bind_group) =
+            bindings(device, declarations, &buffers, |_| ShaderStages::COMPUTE);
+        let (render_layout, render_bind_group) =
+            bindings(device, declarations, &buffers, |binding| {
+                visibility
+                    .get(&binding)
+                    .map_or(ShaderStages::NONE, |stages| {
+                        stages.difference(ShaderStages::COMPUTE)
+                    })
+            });
+        let (empty_layout, empty_bind_group) = bindings(device, &[], &[], |_| ShaderStages::NONE);
+
+        Some(Storage {
+            buffers,
+            compute_bind_group,
+            compute_layout,
+            empty_bind_group,
+            empty_layout,
+            render_bind_group,
+            render_layout,
+        })
+    }
+
+    /// Records copying every buffer aside, for drawing the same frame more
+    /// than once with [`Storage::restore`] in between, as tiled exports do.
+    #[must_use]
+    pub fn snapshot(&self, device: &Device, encoder: &mut CommandEncoder) -> Vec<Buffer> {
+        self.buffers
+            .iter()
+            .map(|buffer| {
+                let copy = device.create_buffer(&BufferDescriptor {
+                    label: Some("storage_snapshot"),
+                    size: buffer.size(),
+                    usage: BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                });
+                encoder.copy_buffer_to_buffer(buffer, 0, &copy, 0, buffer.size());
+                copy
+            })
+            .collect()
+    }
+
+    /// Records copying a [`Storage::snapshot`] back into the buffers.
+    pub fn restore(&self, encoder: &mut CommandEncoder, snapshot: &[Buffer]) {
+        for (buffer, copy) in self.buffers.iter().zip(snapshot) {
+            encoder.copy_buffer_to_buffer(copy, 0, buffer, 0, buffer.size());
+        }
+    }
+}
+
+/// Layout and bind group of every buffer for the stages `visibility` gives
+/// each binding, writable only from compute.
+fn bindings(
+    device: &Device,
+    declarations: &[Declaration],
+    buffers: &[Buffer],
+    visibility: impl Fn(u32) -> ShaderStages,
+) -> (BindGroupLayout, BindGroup) {
+    let entries: Vec<_> = declarations
+        .iter()
+        .map(|declaration| BindGroupLayoutEntry {
+            binding: declaration.binding,
+            visibility: visibility(declaration.binding),
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Storage {
+                    read_only: visibility(declaration.binding) != ShaderStages::COMPUTE,
+                },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        })
+        .collect();
+    let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+        label: Some("storage_layout"),
+        entries: &entries,
+    });
+
+    let entries: Vec<_> = declarations
+        .iter()
+        .zip(buffers)
+        .map(|(declaration, buffer)| BindGroupEntry {
+            binding: declaration.binding,
+            resource: buffer.as_entire_binding(),
+        })
+        .collect();
+    let bind_group = device.create_bind_group(&BindGroupDescriptor {
+        label: Some("storage_bind_group"),
+        layout: &layout,
+        entries: &entries,
+    });
+
+    (layout, bind_group)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// [`declarations`] of `code`, with `files` written as the contents files
+    /// next to it, in a directory of its own named after `test`.
+    fn declare(
+        test: &str,
+        code: &str,
+        files: &[(&str, &[u8])],
+    ) -> Result<Vec<Declaration>, SetupError> {
+        let directory = std::env::temp_dir().join(format!(
+            "ray_marching_storage_{test}_{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&directory).expect("the temporary directory can be created");
+        for (name, bytes) in files {
+            fs::write(directory.join(name), bytes).expect("the contents file can be written");
+        }
+
+        let module = naga::front::wgsl::parse_str(code).expect("the snippet parses");
+        let declared = declarations(&module, Some(&directory));
+        fs::remove_dir_all(&directory).expect("the temporary directory can be removed");
+        declared
+    }
+
+    #[test]
+    fn sizes_come_from_the_wgsl_type() {
+        let code = "
+            @group(2) @binding(0) var<storage, read_write> cells: array<vec4<f32>, 16>;
+            @group(2) @binding(1) var<storage, read_write> seeded: array<u32, 8>;
+            @group(0) @binding(3) var<storage, read_write> elsewhere: array<u32, 8>;
+        ";
+        let declared = declare("typed", code, &[("seeded.bin", &[1, 2, 3, 4])]).unwrap();
+
+        assert_eq!(declared.len(), 2);
+        assert_eq!(
+            (declared[0].name.as_str(), declared[0].size),
+            ("cells", 256)
+        );
+        assert_eq!(declared[0].contents, None);
+        assert_eq!(
+            (declared[1].name.as_str(), declared[1].size),
+            ("seeded", 32)
+        );
+        assert_eq!(declared[1].contents.as_deref(), Some(&[1, 2, 3, 4][..]));
+    }
+
+    #[test]
+    fn runtime_sized_arrays_take_the_size_of_their_file() {
+        let code = "@group(2) @binding(0) var<storage, read_write> points: array<vec4<f32>>;";
+        let declared = declare("runtime", code, &[("points.bin", &[0; 40])]).unwrap();
+        assert_eq!(declared[0].size, 40);
+
+        let declared = declare("runtime_unaligned", code, &[("points.bin", &[0; 3])]).unwrap();
+        assert_eq!(declared[0].size, 16, "at least one element");
+    }
+
+    #[test]
+    fn runtime_sized_arrays_need_a_file() {
+        let code = "@group(2) @binding(0) var<storage, read_write> points: array<vec4<f32>>;";
+        let Err(SetupError::BufferSize { name, reason }) = declare("unsized", code, &[]) else {
+            panic!("a runtime sized array without a file is sized");
+        };
+        assert_eq!(name, "points");
+        assert!(reason.contains(".bin"), "{reason}");
+    }
+
+    #[test]
+    fn contents_have_to_fit() {
+        let code = "@group(2) @binding(0) var<storage, read_write> cells: array<u32, 4>;";
+        let Err(SetupError::BufferSize { name, reason }) =
+            declare("oversized", code, &[("cells.bin", &[0; 20])])
+        else {
+            panic!("20 bytes fit in a 16 byte buffer");
+        };
+        assert_eq!(name, "cells");
+        assert_eq!(reason, "cells.bin has 20 bytes, over 16");
+    }
+
+    #[test]
+    fn bindings_declared_twice_keep_the_writable_declaration() {
+        let code = "
+            @group(2) @binding(0) var<storage, read> particles_in: array<vec4<f32>, 4>;
+            @group(2) @binding(0) var<storage, read_write> particles: array<vec4<f32>, 4>;
+        ";
+        let declared = declare("twice", code, &[("particles.bin", &[7; 16])]).unwrap();
+
+        assert_eq!(declared.len(), 1);
+        assert_eq!(declared[0].name, "particles");
+        assert_eq!(declared[0].binding, 0);
+        assert_eq!(declared[0].contents.as_deref(), Some(&[7; 16][..]));
+    }
+}
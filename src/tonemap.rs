@@ -0,0 +1,283 @@
+use core::error::Error;
+use core::fmt;
+use core::str::FromStr;
+use wgpu::util::DeviceExt as _;
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingResource, BindingType, Buffer, BufferBindingType, BufferUsages,
+    Color, ColorTargetState, ColorWrites, CommandEncoder, Device, Extent3d, FragmentState, LoadOp,
+    MultisampleState, Operations, PipelineCompilationOptions, PipelineLayoutDescriptor,
+    PrimitiveState, Queue, RenderPassColorAttachment, RenderPassDescriptor,
+    RenderPassTimestampWrites, RenderPipeline, RenderPipelineDescriptor, ShaderStages, StoreOp,
+    TextureDescriptor, TextureDimension, TextureFormat, TextureSampleType, TextureUsages,
+    TextureView, TextureViewDescriptor, TextureViewDimension, VertexState, include_wgsl,
+};
+
+/// Format windows and exports render their shaders to, with room above 1.0
+/// and below 0.0 for the tonemapper to bring back into range.
+pub const FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+/// How [`FORMAT`] colors are mapped to what the display shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Operator {
+    /// Clamped to 1.0, as if drawn to the window directly.
+    #[default]
+    None,
+    /// `color / (1 + color)` per channel, never quite reaching white.
+    Reinhard,
+    /// Fit of the ACES filmic curve, contrasty and saturated.
+    Aces,
+    /// Desaturates bright colors toward white rather than shifting their hue.
+    Agx,
+}
+
+impl Operator {
+    #[must_use]
+    pub fn next(self) -> Operator {
+        match self {
+            Operator::None => Operator::Reinhard,
+            Operator::Reinhard => Operator::Aces,
+            Operator::Aces => Operator::Agx,
+            Operator::Agx => Operator::None,
+        }
+    }
+
+    /// The value `tonemap.wgsl` switches on.
+    fn index(self) -> u32 {
+        match self {
+            Operator::None => 0,
+            Operator::Reinhard => 1,
+            Operator::Aces => 2,
+            Operator::Agx => 3,
+        }
+    }
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(match self {
+            Operator::None => "none",
+            Operator::Reinhard => "reinhard",
+            Operator::Aces => "aces",
+            Operator::Agx => "agx",
+        })
+    }
+}
+
+impl FromStr for Operator {
+    type Err = Box<dyn Error>;
+
+    fn from_str(name: &str) -> Result<Operator, Self::Err> {
+        match name {
+            "none" => Ok(Operator::None),
+            "reinhard" => Ok(Operator::Reinhard),
+            "aces" => Ok(Operator::Aces),
+            "agx" => Ok(Operator::Agx),
+            _ => Err(
+                format!("unknown tonemapper `{name}`, expected none, reinhard, aces or agx").into(),
+            ),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct TonemapUniforms {
+    curve: u32,
+    exposure: f32,
+}
+
+/// The [`FORMAT`] target shaders are drawn into, sized as the window or an
+/// export tile, and the pass that tonemaps it onto the window or tile.
+#[derive(Debug)]
+pub struct Tonemap {
+    pub operator: Operator,
+    /// Stops the image is brightened by before [`Tonemap::operator`],
+    /// negative to darken it.
+    pub exposure: f32,
+    bind_group: BindGroup,
+    bind_group_layout: BindGroupLayout,
+    pipeline: RenderPipeline,
+    target: TextureView,
+    uniforms_buffer: Buffer,
+}
+
+impl Tonemap {
+    /// `format` is the one the result is written as, and `size` the size of
+    /// the window or tile in pixels.
+    #[must_use]
+    pub fn new(device: &Device, format: TextureFormat, size: [u32; 2]) -> Tonemap {
+        let fullscreen = device.create_shader_module(include_wgsl!("fullscreen.wgsl"));
+        let shader = device.create_shader_module(include_wgsl!("tonemap.wgsl"));
+
+        let uniforms_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("tonemap_uniforms_buffer"),
+            contents: bytemuck::bytes_of(&TonemapUniforms {
+                curve: Operator::default().index(),
+                exposure: 0.0,
+            }),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("tonemap_bindgroup_layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: false },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("tonemap_pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("tonemap_pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &fullscreen,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: PipelineCompilationOptions::default(),
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let target = target(device, size);
+        let bind_group = bind_group(device, &bind_group_layout, &uniforms_buffer, &target);
+
+        Tonemap {
+            operator: Operator::default(),
+            exposure: 0.0,
+            bind_group,
+            bind_group_layout,
+            pipeline,
+            target,
+            uniforms_buffer,
+        }
+    }
+
+    /// The target the window's shaders have to be drawn into.
+    #[must_use]
+    pub fn target(&self) -> &TextureView {
+        &self.target
+    }
+
+    /// Recreates the target for a window or tile of `size` pixels.
+    pub fn resize(&mut self, device: &Device, size: [u32; 2]) {
+        self.target = target(device, size);
+        self.bind_group = bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.uniforms_buffer,
+            &self.target,
+        );
+    }
+
+    /// Records the pass tonemapping the target into `view`.
+    pub fn draw(
+        &self,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        view: &TextureView,
+        timestamp_writes: Option<RenderPassTimestampWrites<'_>>,
+    ) {
+        let uniforms = TonemapUniforms {
+            curve: self.operator.index(),
+            exposure: self.exposure,
+        };
+        queue.write_buffer(&self.uniforms_buffer, 0, bytemuck::bytes_of(&uniforms));
+
+        let mut renderpass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("tonemap"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(Color::BLACK),
+                    store: StoreOp::Store,
+                },
+            })],
+            timestamp_writes,
+            ..Default::default()
+        });
+        renderpass.set_pipeline(&self.pipeline);
+        renderpass.set_bind_group(0, &self.bind_group, &[]);
+        renderpass.draw(0..3, 0..1);
+    }
+}
+
+/// View of a [`FORMAT`] target, at least one pixel in size.
+fn target(device: &Device, size: [u32; 2]) -> TextureView {
+    device
+        .create_texture(&TextureDescriptor {
+            label: Some("tonemap_target"),
+            size: Extent3d {
+                width: size[0].max(1),
+                height: size[1].max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: FORMAT,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        })
+        .create_view(&TextureViewDescriptor::default())
+}
+
+fn bind_group(
+    device: &Device,
+    layout: &BindGroupLayout,
+    uniforms_buffer: &Buffer,
+    target: &TextureView,
+) -> BindGroup {
+    device.create_bind_group(&BindGroupDescriptor {
+        label: Some("tonemap_bind_group"),
+        layout,
+        entries: &[
+            BindGroupEntry {
+                binding: 0,
+                resource: uniforms_buffer.as_entire_binding(),
+            },
+            BindGroupEntry {
+                binding: 1,
+                resource: BindingResource::TextureView(target),
+            },
+        ],
+    })
+}
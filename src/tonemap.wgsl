@@ -0,0 +1,68 @@
+struct Tonemap {
+    // 0 none, 1 Reinhard, 2 ACES, 3 AgX.
+    curve: u32,
+    // In stops, applied before the curve.
+    exposure: f32,
+}
+
+@group(0) @binding(0) var<uniform> tonemap: Tonemap;
+@group(0) @binding(1) var hdr: texture_2d<f32>;
+
+// Krzysztof Narkowicz's fit of the ACES filmic curve.
+fn aces(color: vec3<f32>) -> vec3<f32> {
+    let fitted = (color * (2.51 * color + 0.03)) / (color * (2.43 * color + 0.59) + 0.14);
+    return clamp(fitted, vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+// Polynomial fit of the AgX base contrast curve, from Benjamin Wrensch's
+// minimal AgX.
+fn agx_contrast(x: vec3<f32>) -> vec3<f32> {
+    let x2 = x * x;
+    let x4 = x2 * x2;
+    return 15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2
+        + 0.1191 * x - 0.00232;
+}
+
+// Troy Sobotka's AgX: the color is pulled toward the achromatic axis, so
+// bright saturated colors desaturate to white instead of skewing in hue,
+// compressed in log2 between MIN_EV and MAX_EV and brought back to linear.
+fn agx(color: vec3<f32>) -> vec3<f32> {
+    const MIN_EV = -12.47393;
+    const MAX_EV = 4.026069;
+    let inset = mat3x3<f32>(
+        vec3<f32>(0.842479062253094, 0.0423282422610123, 0.0423756549057051),
+        vec3<f32>(0.0784335999999992, 0.878468636469772, 0.0784336),
+        vec3<f32>(0.0792237451477643, 0.0791661274605434, 0.879142973793104),
+    );
+    let outset = mat3x3<f32>(
+        vec3<f32>(1.19687900512017, -0.0528968517574562, -0.0529716355144438),
+        vec3<f32>(-0.0980208811401368, 1.15190312990417, -0.0980434501171241),
+        vec3<f32>(-0.0990297440797205, -0.0989611768448433, 1.15107367264116),
+    );
+
+    let log = clamp(log2(max(inset * color, vec3<f32>(1e-10))), vec3<f32>(MIN_EV), vec3<f32>(MAX_EV));
+    let curved = agx_contrast((log - MIN_EV) / (MAX_EV - MIN_EV));
+    return pow(max(outset * curved, vec3<f32>(0.0)), vec3<f32>(2.2));
+}
+
+// Linear light out, the sRGB view of the target encodes it.
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let texel = textureLoad(hdr, vec2<i32>(position.xy), 0);
+    let color = max(texel.rgb * exp2(tonemap.exposure), vec3<f32>(0.0));
+
+    switch tonemap.curve {
+        case 1u: {
+            return vec4<f32>(color / (1.0 + color), texel.a);
+        }
+        case 2u: {
+            return vec4<f32>(aces(color), texel.a);
+        }
+        case 3u: {
+            return vec4<f32>(agx(color), texel.a);
+        }
+        default: {
+            return vec4<f32>(min(color, vec3<f32>(1.0)), texel.a);
+        }
+    }
+}
@@ -0,0 +1,324 @@
+use crate::error::SetupError;
+use naga::{Binding, ScalarKind, TypeInner};
+use wgpu::{VertexAttribute, VertexBufferLayout, VertexFormat, VertexStepMode};
+
+/// A field type a vertex buffer can feed to a shader input.
+pub trait Attribute {
+    const FORMAT: VertexFormat;
+}
+
+macro_rules! attribute {
+    ($($ty:ty => $format:ident),* $(,)?) => {
+        $(impl Attribute for $ty {
+            const FORMAT: VertexFormat = VertexFormat::$format;
+        })*
+    };
+}
+
+attribute! {
+    f32 => Float32, [f32; 2] => Float32x2, [f32; 3] => Float32x3, [f32; 4] => Float32x4,
+    u32 => Uint32, [u32; 2] => Uint32x2, [u32; 3] => Uint32x3, [u32; 4] => Uint32x4,
+    i32 => Sint32, [i32; 2] => Sint32x2, [i32; 3] => Sint32x3, [i32; 4] => Sint32x4,
+}
+
+/// A vertex type with its fields read as `@location(0)`, `@location(1)`...
+/// in declaration order. Implemented by declaring the struct with
+/// [`vertex!`](crate::vertex::vertex), so fields and layout can't drift
+/// apart.
+pub trait VertexLayout: bytemuck::Pod {
+    const ATTRIBUTES: &'static [VertexAttribute];
+
+    #[must_use]
+    fn layout() -> VertexBufferLayout<'static> {
+        VertexBufferLayout {
+            array_stride: size_of::<Self>() as u64,
+            step_mode: VertexStepMode::Vertex,
+            attributes: Self::ATTRIBUTES,
+        }
+    }
+}
+
+/// Declares a `#[repr(C)]` [`bytemuck::Pod`] struct and implements
+/// [`VertexLayout`] for it from its fields, which have to be [`Attribute`]s.
+macro_rules! vertex {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($(#[$field_meta:meta])* $field_vis:vis $field:ident: $ty:ty),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[repr(C)]
+        #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+        $vis struct $name {
+            $($(#[$field_meta])* $field_vis $field: $ty),*
+        }
+
+        impl $crate::vertex::VertexLayout for $name {
+            const ATTRIBUTES: &'static [wgpu::VertexAttribute] = &{
+                let mut attributes = [$(wgpu::VertexAttribute {
+                    format: <$ty as $crate::vertex::Attribute>::FORMAT,
+                    offset: core::mem::offset_of!($name, $field) as u64,
+                    shader_location: 0,
+                }),*];
+                let mut index = 0;
+                let mut location = 0;
+                while index < attributes.len() {
+                    attributes[index].shader_location = location;
+                    index += 1;
+                    location += 1;
+                }
+                attributes
+            };
+        }
+    };
+}
+pub(crate) use vertex;
+
+/// Checks that every `@location` input of the `vs_main` of `module` is fed
+/// by an attribute of `layout` with the same kind of scalar, as wgpu only
+/// finds out once the pipeline is created and then can't tell which shader
+/// it was. `None` stands for drawing with no vertex buffer at all.
+///
+/// The component counts may differ, missing components read as 0 and a
+/// missing `w` as 1.
+///
+/// # Errors
+/// [`SetupError::VertexInput`] naming the first input that doesn't match.
+pub fn check(
+    name: &str,
+    module: &naga::Module,
+    layout: Option<&VertexBufferLayout<'_>>,
+) -> Result<(), SetupError> {
+    let Some(entry_point) = module
+        .entry_points
+        .iter()
+        .find(|entry_point| entry_point.name == "vs_main")
+    else {
+        return Ok(());
+    };
+
+    let mut inputs = Vec::new();
+    for argument in &entry_point.function.arguments {
+        match (&argument.binding, &module.types[argument.ty].inner) {
+            (Some(Binding::Location { location, .. }), _) => inputs.push((*location, argument.ty)),
+            (None, TypeInner::Struct { members, .. }) => {
+                inputs.extend(members.iter().filter_map(|member| match member.binding {
+                    Some(Binding::Location { location, .. }) => Some((location, member.ty)),
+                    _ => None,
+                }));
+            }
+            _ => {}
+        }
+    }
+
+    let attributes = layout.map_or(&[][..], |layout| layout.attributes);
+    for (location, ty) in inputs {
+        let error = |reason| SetupError::VertexInput {
+            name: name.to_owned(),
+            reason,
+        };
+        let Some(attribute) = attributes
+            .iter()
+            .find(|attribute| attribute.shader_location == location)
+        else {
+            return Err(error(format!(
+                "vs_main reads @location({location}), which the vertices don't have"
+            )));
+        };
+        let kind = module.types[ty].inner.scalar_kind();
+        if kind != Some(scalar_kind(attribute.format)) {
+            return Err(error(format!(
+                "vs_main reads @location({location}) as {}, but the vertices have {:?} there",
+                kind.map_or("something else", kind_name),
+                attribute.format
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// What a shader reads an attribute of `format` as.
+fn scalar_kind(format: VertexFormat) -> ScalarKind {
+    match format {
+        VertexFormat::Uint8
+        | VertexFormat::Uint8x2
+        | VertexFormat::Uint8x4
+        | VertexFormat::Uint16
+        | VertexFormat::Uint16x2
+        | VertexFormat::Uint16x4
+        | VertexFormat::Uint32
+        | VertexFormat::Uint32x2
+        | VertexFormat::Uint32x3
+        | VertexFormat::Uint32x4 => ScalarKind::Uint,
+        VertexFormat::Sint8
+        | VertexFormat::Sint8x2
+        | VertexFormat::Sint8x4
+        | VertexFormat::Sint16
+        | VertexFormat::Sint16x2
+        | VertexFormat::Sint16x4
+        | VertexFormat::Sint32
+        | VertexFormat::Sint32x2
+        | VertexFormat::Sint32x3
+        | VertexFormat::Sint32x4 => ScalarKind::Sint,
+        _ => ScalarKind::Float,
+    }
+}
+
+/// Plural of the values a shader input of `kind` holds, for errors.
+fn kind_name(kind: ScalarKind) -> &'static str {
+    match kind {
+        ScalarKind::Float | ScalarKind::AbstractFloat => "floats",
+        ScalarKind::Uint => "unsigned integers",
+        ScalarKind::Sint | ScalarKind::AbstractInt => "signed integers",
+        ScalarKind::Bool => "booleans",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{MeshVertex, Vertex};
+
+    vertex! {
+        struct Probe {
+            position: [f32; 3],
+            id: u32,
+            offset: [i32; 2],
+        }
+    }
+
+    fn check_wgsl(code: &str, layout: Option<&VertexBufferLayout<'_>>) -> Result<(), String> {
+        let module = naga::front::wgsl::parse_str(code).expect("the snippet parses");
+        check("probe.wgsl", &module, layout).map_err(|error| error.to_string())
+    }
+
+    #[test]
+    fn matching_inputs_are_fine() {
+        let code = "
+            struct Input { @location(0) position: vec2<f32>, @location(2) offset: vec2<i32> }
+            @vertex
+            fn vs_main(input: Input, @location(1) id: u32) -> @builtin(position) vec4<f32> {
+                return vec4<f32>(input.position, 0.0, 1.0);
+            }
+        ";
+        assert_eq!(check_wgsl(code, Some(&Probe::layout())), Ok(()));
+    }
+
+    #[test]
+    fn builtin_inputs_need_no_vertices() {
+        let code = "
+            @vertex
+            fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+                return vec4<f32>(f32(index), 0.0, 0.0, 1.0);
+            }
+        ";
+        assert_eq!(check_wgsl(code, None), Ok(()));
+        assert_eq!(check_wgsl(code, Some(&Probe::layout())), Ok(()));
+    }
+
+    #[test]
+    fn missing_locations_are_rejected() {
+        let code = "
+            @vertex
+            fn vs_main(@location(3) uv: vec2<f32>) -> @builtin(position) vec4<f32> {
+                return vec4<f32>(uv, 0.0, 1.0);
+            }
+        ";
+        let expected = "vs_main reads @location(3), which the vertices don't have";
+        let error = check_wgsl(code, Some(&Probe::layout())).unwrap_err();
+        assert!(error.contains(expected), "{error}");
+        let error = check_wgsl(code, None).unwrap_err();
+        assert!(error.contains(expected), "{error}");
+    }
+
+    #[test]
+    fn scalar_kinds_have_to_match() {
+        let float_as_int = "
+            @vertex
+            fn vs_main(@location(0) position: vec3<i32>) -> @builtin(position) vec4<f32> {
+                return vec4<f32>(vec3<f32>(position), 1.0);
+            }
+        ";
+        let error = check_wgsl(float_as_int, Some(&Probe::layout())).unwrap_err();
+        assert!(
+            error.contains("@location(0) as signed integers, but the vertices have Float32x3"),
+            "{error}"
+        );
+
+        let uint_as_float = "
+            @vertex
+            fn vs_main(@location(1) id: f32) -> @builtin(position) vec4<f32> {
+                return vec4<f32>(id, 0.0, 0.0, 1.0);
+            }
+        ";
+        let error = check_wgsl(uint_as_float, Some(&Probe::layout())).unwrap_err();
+        assert!(
+            error.contains("@location(1) as floats, but the vertices have Uint32"),
+            "{error}"
+        );
+
+        let sint_as_uint = "
+            @vertex
+            fn vs_main(@location(2) offset: vec2<u32>) -> @builtin(position) vec4<f32> {
+                return vec4<f32>(vec2<f32>(offset), 0.0, 1.0);
+            }
+        ";
+        let error = check_wgsl(sint_as_uint, Some(&Probe::layout())).unwrap_err();
+        assert!(
+            error.contains("as unsigned integers, but the vertices have Sint32x2"),
+            "{error}"
+        );
+    }
+
+    /// Format, offset and location of each attribute of `layout`.
+    fn attributes(layout: &VertexBufferLayout<'_>) -> Vec<(VertexFormat, u64, u32)> {
+        layout
+            .attributes
+            .iter()
+            .map(|attribute| {
+                (
+                    attribute.format,
+                    attribute.offset,
+                    attribute.shader_location,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn layouts_follow_the_fields() {
+        let vertex = Vertex::layout();
+        assert_eq!(vertex.array_stride, 24);
+        assert_eq!(vertex.step_mode, VertexStepMode::Vertex);
+        assert_eq!(
+            attributes(&vertex),
+            [
+                (VertexFormat::Float32x3, 0, 0),
+                (VertexFormat::Float32x3, 12, 1)
+            ]
+        );
+
+        let mesh = MeshVertex::layout();
+        assert_eq!(mesh.array_stride, 32);
+        assert_eq!(
+            attributes(&mesh),
+            [
+                (VertexFormat::Float32x3, 0, 0),
+                (VertexFormat::Float32x3, 12, 1),
+                (VertexFormat::Float32x2, 24, 2),
+            ]
+        );
+
+        let probe = Probe::layout();
+        assert_eq!(probe.array_stride, 24);
+        assert_eq!(
+            attributes(&probe),
+            [
+                (VertexFormat::Float32x3, 0, 0),
+                (VertexFormat::Uint32, 12, 1),
+                (VertexFormat::Sint32x2, 16, 2),
+            ]
+        );
+    }
+}
@@ -0,0 +1,31 @@
+// Default shader for `--vertices`: a spiral of points placed from the vertex
+// index alone.
+
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+
+const TURNS = 12.0;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let along = f32(index) / f32(max(uniforms.vertex_count, 1u));
+    let angle = along * TURNS * 6.2831853 + uniforms.time * 0.3;
+    let radius = sqrt(along) * (0.9 + 0.05 * sin(along * 40.0 + uniforms.time * 2.0));
+
+    var position = vec2<f32>(cos(angle), sin(angle)) * radius;
+    position.x *= uniforms.resolution.y / max(uniforms.resolution.x, 1.0);
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(position, 0.0, 1.0);
+    out.color = 0.5 + 0.5 * cos(6.2831853 * (along + vec3<f32>(0.0, 0.33, 0.67)));
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.color, 1.0);
+}